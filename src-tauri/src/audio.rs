@@ -0,0 +1,385 @@
+//! Cross-platform audio capture pipeline.
+//!
+//! Owns everything between the microphone and the WAV file:
+//! - enumerating and opening input devices (`cpal`)
+//! - converting I16/U16/F32 device samples to 16-bit PCM
+//! - applying mic gain
+//! - downmixing/resampling to 16 kHz mono ([`crate::dsp`])
+//! - encoding the result as WAV in the temp dir
+//!
+//! Platform modules only pass their quirks via [`CaptureOptions`]
+//! (e.g. the Windows warm-up delay).
+
+use crate::dsp;
+use crate::platform::RecordingHandle;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::Serialize;
+
+/// Per-recording settings plus platform quirks.
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    /// Multiplier applied to samples (1.0 = no change, >1.0 = louder).
+    pub gain: f32,
+
+    /// Input device name as reported by [`list_input_devices`].
+    /// `None` (or a device that is no longer present) uses the default device.
+    pub device_name: Option<String>,
+
+    /// Time to keep the stream running before reporting that capture has
+    /// started. Some backends (WASAPI) drop the first buffers after opening.
+    pub warm_up: Option<Duration>,
+}
+
+impl CaptureOptions {
+    pub fn new(gain: f32) -> Self {
+        Self {
+            gain,
+            device_name: None,
+            warm_up: None,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Device enumeration
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// An input device and the stream configurations it supports.
+#[derive(Debug, Serialize, Clone)]
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub configs: Vec<InputConfigInfo>,
+}
+
+/// One supported configuration range of an input device.
+#[derive(Debug, Serialize, Clone)]
+pub struct InputConfigInfo {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// List the input devices of the default host.
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+
+    let devices = host
+        .input_devices()
+        .context("Failed to enumerate input devices")?;
+
+    Ok(devices
+        .filter_map(|device| {
+            let name = device.name().ok()?;
+            let configs = device
+                .supported_input_configs()
+                .map(|configs| {
+                    configs
+                        .map(|c| InputConfigInfo {
+                            channels: c.channels(),
+                            min_sample_rate: c.min_sample_rate().0,
+                            max_sample_rate: c.max_sample_rate().0,
+                            sample_format: c.sample_format().to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(InputDeviceInfo {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                configs,
+            })
+        })
+        .collect())
+}
+
+/// Whether an input device with this name is currently present.
+pub fn input_device_exists(name: &str) -> bool {
+    find_input_device(&cpal::default_host(), name).is_some()
+}
+
+fn find_input_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    host.input_devices()
+        .ok()?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Sample conversion
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Convert a signed 16-bit sample, applying `gain`.
+pub fn convert_i16(s: i16, gain: f32) -> i16 {
+    (s as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Convert an unsigned 16-bit sample (silence at 32768), applying `gain`.
+pub fn convert_u16(s: u16, gain: f32) -> i16 {
+    let f = (s as f32 / 32768.0 - 1.0) * gain;
+    (f.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Convert a float sample in [-1.0, 1.0], applying `gain`.
+pub fn convert_f32(s: f32, gain: f32) -> i16 {
+    let amplified = s * gain;
+    (amplified.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Encoding
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A fresh `groqtranscriber-<timestamp>.wav` path in the temp dir.
+pub fn temp_wav_path() -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let filename = format!(
+        "groqtranscriber-{}.wav",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    path.push(filename);
+    path
+}
+
+/// Write interleaved 16-bit PCM samples as a WAV file.
+pub fn write_wav(path: &Path, samples: &[i16], sample_rate: u32, channels: u16) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).context("Failed to create wav")?;
+    for &s in samples {
+        writer.write_sample(s).ok();
+    }
+    writer.finalize().context("Failed to finalize wav")?;
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Recording session
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Samples captured so far, shared with the cpal callback.
+type SampleBuffer = Arc<Mutex<Vec<i16>>>;
+
+/// A Send handle for an in-progress recording.
+///
+/// The actual `cpal::Stream` is created and owned inside a dedicated thread
+/// because `cpal::Stream` is not `Send`/`Sync` on all platforms.
+pub struct RecordingSession {
+    stop_tx: mpsc::Sender<()>,
+    done_rx: mpsc::Receiver<Result<PathBuf>>,
+}
+
+impl RecordingSession {
+    /// Start capturing from the configured (or default) input device.
+    ///
+    /// Blocks until audio is actually flowing (including any warm-up), so the
+    /// caller knows it's safe to show "Recording" to the user.
+    pub fn start(options: CaptureOptions) -> Result<Self> {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let (done_tx, done_rx) = mpsc::channel::<Result<PathBuf>>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+
+        thread::spawn(move || match open_stream(&options) {
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                let _ = done_tx.send(Err(e));
+            }
+            Ok((stream, samples, sample_rate, channels)) => {
+                let _ = ready_tx.send(Ok(()));
+
+                // Block until stop signal.
+                let _ = stop_rx.recv();
+                drop(stream);
+
+                let res = samples
+                    .lock()
+                    .map_err(|_| anyhow!("Failed to lock samples"))
+                    .and_then(|samples| {
+                        let path = temp_wav_path();
+                        let mono = dsp::to_whisper_format(&samples, sample_rate, channels);
+                        write_wav(&path, &mono, dsp::WHISPER_SAMPLE_RATE, 1)?;
+                        Ok(path)
+                    });
+                let _ = done_tx.send(res);
+            }
+        });
+
+        ready_rx
+            .recv()
+            .map_err(|_| anyhow!("Recording thread terminated during init"))?
+            .map_err(|e| anyhow!("{e}"))?;
+
+        Ok(Self { stop_tx, done_rx })
+    }
+}
+
+impl RecordingHandle for RecordingSession {
+    fn stop_and_save_wav(self: Box<Self>) -> Result<PathBuf, String> {
+        let _ = self.stop_tx.send(());
+        self.done_rx
+            .recv()
+            .map_err(|_| "Recording thread terminated unexpectedly".to_string())?
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Open the input device and start a stream that converts into `SampleBuffer`.
+fn open_stream(options: &CaptureOptions) -> Result<(cpal::Stream, SampleBuffer, u32, u16)> {
+    let host = cpal::default_host();
+    let selected = options
+        .device_name
+        .as_deref()
+        .and_then(|name| find_input_device(&host, name));
+    let device = match selected {
+        Some(device) => device,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| anyhow!("No default input device"))?,
+    };
+
+    let supported_config = device
+        .default_input_config()
+        .context("Failed to get default input config")?;
+
+    let sample_rate = supported_config.sample_rate().0;
+    let channels = supported_config.channels();
+    let sample_format = supported_config.sample_format();
+    let config: cpal::StreamConfig = supported_config.into();
+
+    let samples: SampleBuffer = Arc::new(Mutex::new(Vec::new()));
+    let gain = options.gain;
+
+    let stream = match sample_format {
+        cpal::SampleFormat::I16 => {
+            build_stream(&device, &config, samples.clone(), move |s| convert_i16(s, gain))?
+        }
+        cpal::SampleFormat::U16 => {
+            build_stream(&device, &config, samples.clone(), move |s| convert_u16(s, gain))?
+        }
+        cpal::SampleFormat::F32 => {
+            build_stream(&device, &config, samples.clone(), move |s| convert_f32(s, gain))?
+        }
+        other => return Err(anyhow!("Unsupported sample format: {other:?}")),
+    };
+
+    stream.play()?;
+
+    if let Some(warm_up) = options.warm_up {
+        thread::sleep(warm_up);
+    }
+
+    Ok((stream, samples, sample_rate, channels))
+}
+
+fn build_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: SampleBuffer,
+    convert: F,
+) -> Result<cpal::Stream>
+where
+    T: cpal::SizedSample,
+    F: Fn(T) -> i16 + Send + 'static,
+{
+    let err_fn = |err| eprintln!("an error occurred on the input audio stream: {err}");
+
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            if let Ok(mut buf) = samples.lock() {
+                buf.extend(data.iter().map(|&s| convert(s)));
+            }
+        },
+        err_fn,
+        None,
+    )?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i16_passes_through_at_unity_gain() {
+        for s in [i16::MIN, -1234, 0, 1234, i16::MAX] {
+            assert_eq!(convert_i16(s, 1.0), s);
+        }
+    }
+
+    #[test]
+    fn i16_applies_gain_and_clamps() {
+        assert_eq!(convert_i16(1000, 2.0), 2000);
+        assert_eq!(convert_i16(-1000, 0.5), -500);
+        assert_eq!(convert_i16(20000, 2.0), i16::MAX);
+        assert_eq!(convert_i16(-20000, 2.0), i16::MIN);
+    }
+
+    #[test]
+    fn u16_midpoint_is_silence() {
+        assert_eq!(convert_u16(32768, 1.0), 0);
+        assert_eq!(convert_u16(32768, 3.0), 0);
+    }
+
+    #[test]
+    fn u16_extremes_map_to_full_scale() {
+        assert_eq!(convert_u16(0, 1.0), -i16::MAX);
+        assert!(convert_u16(u16::MAX, 1.0) >= i16::MAX - 1);
+    }
+
+    #[test]
+    fn u16_applies_gain_and_clamps() {
+        let quiet = convert_u16(32768 + 1000, 1.0);
+        let loud = convert_u16(32768 + 1000, 2.0);
+        assert!((loud as i32 - 2 * quiet as i32).abs() <= 2);
+        assert_eq!(convert_u16(60000, 4.0), i16::MAX);
+        assert_eq!(convert_u16(1000, 4.0), -i16::MAX);
+    }
+
+    #[test]
+    fn f32_scales_to_i16_range() {
+        assert_eq!(convert_f32(0.0, 1.0), 0);
+        assert_eq!(convert_f32(1.0, 1.0), i16::MAX);
+        assert_eq!(convert_f32(-1.0, 1.0), -i16::MAX);
+        assert_eq!(convert_f32(0.5, 1.0), i16::MAX / 2);
+    }
+
+    #[test]
+    fn f32_applies_gain_and_clamps() {
+        assert_eq!(convert_f32(0.25, 2.0), i16::MAX / 2);
+        assert_eq!(convert_f32(0.9, 2.0), i16::MAX);
+        assert_eq!(convert_f32(-0.9, 2.0), -i16::MAX);
+    }
+
+    #[test]
+    fn write_wav_round_trips() {
+        let path = std::env::temp_dir().join(format!(
+            "groqtranscriber-test-{}.wav",
+            std::process::id()
+        ));
+        let samples = [0i16, 100, -100, i16::MAX, i16::MIN, 42];
+        write_wav(&path, &samples, 48_000, 2).unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.sample_rate, 48_000);
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.bits_per_sample, 16);
+        let read: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(read, samples);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,332 @@
+//! Splitting long recordings into segments and stitching the transcripts.
+//!
+//! Segments are cut at the quietest point near the duration limit so words
+//! are rarely split. When no quiet point exists, consecutive segments overlap
+//! a little and [`stitch`] removes the words transcribed twice.
+
+use std::ops::Range;
+
+/// Energy is measured over 20 ms frames.
+const FRAME_MS: usize = 20;
+
+/// A cut is only searched for in the last part of each segment, so segments
+/// stay reasonably close to the limit.
+const SEARCH_FROM: f64 = 0.6;
+
+/// Frames quieter than this (RMS, 16-bit scale, about -40 dBFS) are treated
+/// as silence; cutting there needs no overlap.
+const SILENCE_RMS: f64 = 330.0;
+
+/// Longest run of duplicated words looked for at a boundary.
+const MAX_OVERLAP_WORDS: usize = 12;
+
+/// CJK text has no spaces to split words at, so overlaps there are matched
+/// by character: at least this many (one character repeats by chance too
+/// often)...
+const MIN_OVERLAP_CHARS: usize = 2;
+/// ...and at most this many.
+const MAX_OVERLAP_CHARS: usize = 24;
+
+/// A slice of the recording to transcribe on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub range: Range<usize>,
+    /// Whether the start repeats audio from the end of the previous segment.
+    pub overlaps_previous: bool,
+}
+
+/// Split `samples` into segments of at most `max_len` samples, cutting at the
+/// lowest-energy frame in the last 40% of each segment.
+///
+/// If that frame isn't silent, the next segment starts `overlap` samples
+/// before the cut so a word spanning it is heard whole at least once.
+pub fn split_at_silence(
+    samples: &[i16],
+    sample_rate: u32,
+    max_len: usize,
+    overlap: usize,
+) -> Vec<Segment> {
+    let max_len = max_len.max(1);
+    // Keep overlap small relative to the segment so every step makes progress.
+    let overlap = overlap.min(max_len / 4);
+    let frame = (sample_rate as usize * FRAME_MS / 1000).max(1);
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut overlaps_previous = false;
+
+    while samples.len() - start > max_len {
+        let end = start + max_len;
+        let search_start = start + (max_len as f64 * SEARCH_FROM) as usize;
+        let (cut, rms) = quietest_frame(samples, search_start, end, frame);
+
+        segments.push(Segment {
+            range: start..cut,
+            overlaps_previous,
+        });
+
+        overlaps_previous = rms >= SILENCE_RMS && overlap > 0;
+        start = if overlaps_previous { cut - overlap } else { cut };
+    }
+
+    segments.push(Segment {
+        range: start..samples.len(),
+        overlaps_previous,
+    });
+    segments
+}
+
+/// The centre of the quietest `frame`-sized window within `from..to`, and its RMS.
+fn quietest_frame(samples: &[i16], from: usize, to: usize, frame: usize) -> (usize, f64) {
+    let mut best = (to, f64::MAX);
+    let mut pos = from;
+    while pos + frame <= to {
+        let rms = rms(&samples[pos..pos + frame]);
+        if rms < best.1 {
+            best = (pos + frame / 2, rms);
+        }
+        pos += frame;
+    }
+    best
+}
+
+fn rms(samples: &[i16]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / samples.len() as f64).sqrt()
+}
+
+/// Join segment transcripts in order.
+///
+/// Each item is `(text, overlaps_previous)`. Where segments overlap, the
+/// longest run of words that ends the previous text and starts the next one
+/// (ignoring case and punctuation) is kept only once. When either side of the
+/// seam is CJK, the run is matched by character instead.
+pub fn stitch(parts: &[(String, bool)]) -> String {
+    let mut out = String::new();
+    let mut tail: Vec<&str> = Vec::new();
+
+    for (text, overlaps_previous) in parts {
+        let cjk_seam = *overlaps_previous && is_cjk_seam(&out, text);
+        let text = if cjk_seam {
+            &text[char_overlap_len(&out, text)..]
+        } else {
+            text.as_str()
+        };
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let skip = if *overlaps_previous && !cjk_seam {
+            overlap_len(&tail, &words)
+        } else {
+            0
+        };
+        let rest = &words[skip..];
+        let Some(first) = rest.first() else {
+            continue;
+        };
+
+        if !out.is_empty() && !joins_without_space(&out, first) {
+            out.push(' ');
+        }
+        out.push_str(&rest.join(" "));
+
+        tail.extend_from_slice(rest);
+        let keep_from = tail.len().saturating_sub(MAX_OVERLAP_WORDS);
+        tail.drain(..keep_from);
+    }
+
+    out
+}
+
+/// Number of leading words of `next` that repeat the trailing words of `prev`.
+fn overlap_len(prev: &[&str], next: &[&str]) -> usize {
+    let max = MAX_OVERLAP_WORDS.min(prev.len()).min(next.len());
+    (1..=max)
+        .rev()
+        .find(|&k| {
+            prev[prev.len() - k..]
+                .iter()
+                .zip(&next[..k])
+                .all(|(a, b)| normalize_word(a) == normalize_word(b))
+        })
+        .unwrap_or(0)
+}
+
+fn is_cjk_seam(prev: &str, next: &str) -> bool {
+    let last = prev.trim_end().chars().last();
+    let first = next.trim_start().chars().next();
+    last.is_some_and(is_cjk) || first.is_some_and(is_cjk)
+}
+
+/// Byte length of the start of `next` whose letters and digits repeat the
+/// last ones of `prev` (ignoring case, punctuation and spaces), plus the
+/// punctuation `prev` ends with if `next` repeats that too.
+fn char_overlap_len(prev: &str, next: &str) -> usize {
+    let prev_text = prev.trim_end();
+    let mut prev: Vec<char> = prev_text
+        .chars()
+        .rev()
+        .filter(|c| c.is_alphanumeric())
+        .take(MAX_OVERLAP_CHARS)
+        .collect();
+    prev.reverse();
+    let next_chars: Vec<(char, usize)> = next
+        .char_indices()
+        .filter(|(_, c)| c.is_alphanumeric())
+        .take(MAX_OVERLAP_CHARS)
+        .map(|(i, c)| (c, i + c.len_utf8()))
+        .collect();
+    let max = prev.len().min(next_chars.len());
+    (MIN_OVERLAP_CHARS..=max)
+        .rev()
+        .find(|&k| {
+            prev[prev.len() - k..]
+                .iter()
+                .zip(&next_chars[..k])
+                .all(|(a, (b, _))| a.to_lowercase().eq(b.to_lowercase()))
+        })
+        .map_or(0, |k| {
+            let end = next_chars[k - 1].1;
+            let punctuation = prev_text.trim_end_matches(|c: char| !c.is_alphanumeric());
+            let punctuation = &prev_text[punctuation.len()..];
+            if !punctuation.is_empty() && next[end..].starts_with(punctuation) {
+                end + punctuation.len()
+            } else {
+                end
+            }
+        })
+}
+
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// CJK text has no spaces between words, so don't add one at the seam.
+fn joins_without_space(prev: &str, next: &str) -> bool {
+    matches!((prev.chars().last(), next.chars().next()), (Some(a), Some(b)) if is_cjk(a) && is_cjk(b))
+}
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3000..=0x30ff      // CJK punctuation, kana
+        | 0x3400..=0x9fff    // CJK ideographs
+        | 0xac00..=0xd7af    // Hangul
+        | 0xf900..=0xfaff    // compatibility ideographs
+        | 0xff00..=0xffef)   // full-width forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Loud tone with silent gaps of `gap` samples every `period` samples.
+    fn speech_with_gaps(len: usize, period: usize, gap: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| {
+                if i % period < gap {
+                    0
+                } else {
+                    ((i as f32 * 0.1).sin() * 8000.0) as i16
+                }
+            })
+            .collect()
+    }
+
+    fn covered(segments: &[Segment]) -> usize {
+        segments.iter().map(|s| s.range.len()).sum()
+    }
+
+    #[test]
+    fn short_recording_is_one_segment() {
+        let samples = vec![100i16; RATE as usize * 5];
+        let segments = split_at_silence(&samples, RATE, RATE as usize * 10, RATE as usize);
+        assert_eq!(
+            segments,
+            vec![Segment {
+                range: 0..samples.len(),
+                overlaps_previous: false
+            }]
+        );
+    }
+
+    #[test]
+    fn cuts_inside_silent_gaps_without_overlap() {
+        let second = RATE as usize;
+        // 2 s of "speech" followed by 0.5 s of silence, repeated for 60 s.
+        let samples = speech_with_gaps(60 * second, 5 * second / 2, second / 2);
+        let segments = split_at_silence(&samples, RATE, 10 * second, second);
+
+        assert!(segments.len() >= 6);
+        assert_eq!(covered(&segments), samples.len());
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+            assert!(!pair[1].overlaps_previous);
+            let cut = pair[0].range.end;
+            assert!(cut % (5 * second / 2) < second / 2, "cut at {cut} is not in a gap");
+        }
+        assert!(segments.iter().all(|s| s.range.len() <= 10 * second));
+    }
+
+    #[test]
+    fn overlaps_when_there_is_no_silence() {
+        let second = RATE as usize;
+        let samples = speech_with_gaps(30 * second, usize::MAX, 0);
+        let segments = split_at_silence(&samples, RATE, 10 * second, second);
+
+        assert!(segments.len() >= 3);
+        for pair in segments.windows(2) {
+            assert!(pair[1].overlaps_previous);
+            assert_eq!(pair[1].range.start + second, pair[0].range.end);
+        }
+        assert!(segments.iter().all(|s| s.range.len() <= 10 * second));
+        assert_eq!(segments.last().unwrap().range.end, samples.len());
+    }
+
+    #[test]
+    fn stitch_removes_duplicated_words_at_overlaps() {
+        let parts = vec![
+            ("We should ship the new".to_string(), false),
+            ("the New release on Friday, and".to_string(), true),
+            ("and then celebrate.".to_string(), true),
+        ];
+        assert_eq!(
+            stitch(&parts),
+            "We should ship the new release on Friday, and then celebrate."
+        );
+    }
+
+    #[test]
+    fn stitch_keeps_repeats_without_overlap() {
+        let parts = vec![("that that".to_string(), false), ("that is all".to_string(), false)];
+        assert_eq!(stitch(&parts), "that that that is all");
+    }
+
+    #[test]
+    fn stitch_skips_empty_parts_and_joins_cjk_without_space() {
+        let parts = vec![
+            ("今天天气".to_string(), false),
+            ("  ".to_string(), false),
+            ("很好".to_string(), false),
+        ];
+        assert_eq!(stitch(&parts), "今天天气很好");
+    }
+
+    #[test]
+    fn stitch_removes_duplicated_characters_at_cjk_overlaps() {
+        let parts = vec![
+            ("今天天气很好，我们".to_string(), false),
+            ("很好，我们明天去公园。".to_string(), true),
+            ("公园。然后吃饭".to_string(), true),
+        ];
+        assert_eq!(stitch(&parts), "今天天气很好，我们明天去公园。然后吃饭");
+        // A single repeated character is left alone.
+        let parts = vec![("我说的".to_string(), false), ("的确如此".to_string(), true)];
+        assert_eq!(stitch(&parts), "我说的的确如此");
+    }
+}
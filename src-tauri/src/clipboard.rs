@@ -0,0 +1,125 @@
+//! Clipboard-paste text injection.
+//!
+//! Typing synthesizes a key event pair per character, which is slow for long
+//! text and garbled by some apps (terminals, remote desktops, Electron apps
+//! with custom key handling). Pasting instead puts the whole text on the
+//! clipboard, presses the platform paste shortcut, then puts back whatever
+//! the user had copied before.
+//!
+//! Command mode reads the selection the same way in reverse: press the copy
+//! shortcut, read the clipboard, restore it.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use arboard::{Clipboard, ImageData};
+
+/// How long to wait after setting the clipboard before pasting, so clipboard
+/// managers and X11 selection owners have caught up.
+const SETTLE_DELAY: Duration = Duration::from_millis(30);
+
+/// How long the focused app gets to put its selection on the clipboard, and
+/// how often to check whether it has.
+const COPY_TIMEOUT: Duration = Duration::from_millis(500);
+const COPY_POLL: Duration = Duration::from_millis(25);
+
+/// How long the target app gets to read the clipboard before it's restored.
+/// Pasting is asynchronous: the shortcut only queues a key event.
+const PASTE_DELAY: Duration = Duration::from_millis(250);
+
+/// Clipboard contents saved before pasting. Only text and images can be
+/// read back portably; other formats (files, rich text) are lost.
+enum Snapshot {
+    Text(String),
+    Image(ImageData<'static>),
+    Empty,
+}
+
+impl Snapshot {
+    fn take(clipboard: &mut Clipboard) -> Self {
+        if let Ok(text) = clipboard.get_text() {
+            return Snapshot::Text(text);
+        }
+        if let Ok(image) = clipboard.get_image() {
+            return Snapshot::Image(image);
+        }
+        Snapshot::Empty
+    }
+
+    fn restore(self, clipboard: &mut Clipboard) -> Result<(), arboard::Error> {
+        match self {
+            Snapshot::Text(text) => clipboard.set_text(text),
+            Snapshot::Image(image) => clipboard.set_image(image),
+            Snapshot::Empty => clipboard.clear(),
+        }
+    }
+}
+
+/// Paste `text` into the focused app with `press_paste` (the platform paste
+/// shortcut), restoring the previous clipboard contents afterwards.
+///
+/// The clipboard is left alone if something else replaced `text` on it in
+/// the meantime (e.g. the user copied again).
+pub fn paste_text(
+    text: &str,
+    press_paste: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|e| format!("Clipboard init failed: {e}"))?;
+    let snapshot = Snapshot::take(&mut clipboard);
+
+    clipboard
+        .set_text(text.to_string())
+        .map_err(|e| format!("Failed to set clipboard: {e}"))?;
+    thread::sleep(SETTLE_DELAY);
+
+    let pasted = press_paste();
+    if pasted.is_ok() {
+        thread::sleep(PASTE_DELAY);
+    }
+
+    let still_ours = clipboard.get_text().is_ok_and(|current| current == text);
+    let restored = if still_ours {
+        snapshot
+            .restore(&mut clipboard)
+            .map_err(|e| format!("Failed to restore clipboard: {e}"))
+    } else {
+        Ok(())
+    };
+    pasted.and(restored)
+}
+
+/// Read the focused app's selected text by pressing `press_copy` (the
+/// platform copy shortcut), restoring the previous clipboard contents
+/// afterwards. Returns `None` if nothing (or no text) was selected.
+pub fn copy_selection(
+    press_copy: impl FnOnce() -> Result<(), String>,
+) -> Result<Option<String>, String> {
+    let mut clipboard = Clipboard::new().map_err(|e| format!("Clipboard init failed: {e}"))?;
+    let snapshot = Snapshot::take(&mut clipboard);
+
+    // Clear first, so the previous contents aren't mistaken for a selection
+    // when there is none.
+    clipboard
+        .clear()
+        .map_err(|e| format!("Failed to clear clipboard: {e}"))?;
+    let copied = press_copy();
+
+    let mut selection = None;
+    if copied.is_ok() {
+        let deadline = Instant::now() + COPY_TIMEOUT;
+        while Instant::now() < deadline {
+            thread::sleep(COPY_POLL);
+            if let Ok(text) = clipboard.get_text() {
+                if !text.is_empty() {
+                    selection = Some(text);
+                    break;
+                }
+            }
+        }
+    }
+
+    let restored = snapshot
+        .restore(&mut clipboard)
+        .map_err(|e| format!("Failed to restore clipboard: {e}"));
+    copied.and(restored).map(|()| selection)
+}
@@ -0,0 +1,535 @@
+//! Spoken punctuation and formatting commands ("comma", "new line", "all
+//! caps ... end caps", "scratch that", 逗号, 换行, ...).
+//!
+//! The transcript is split into word, punctuation and space tokens, and
+//! command phrases are matched on the words only, so punctuation Whisper
+//! added around a command ("Hello, comma, world.") doesn't get in the way and
+//! is dropped along with it. Everything else is copied through with its
+//! spacing normalized.
+
+use crate::chunking::is_cjk;
+
+/// What a spoken command does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Punctuation attached to the previous word, replacing any punctuation
+    /// Whisper put there.
+    Punctuation(&'static str),
+    /// Line break(s); the next word starts without a space and capitalized.
+    Break(&'static str),
+    OpenQuote(&'static str),
+    CloseQuote(&'static str),
+    CapsOn,
+    CapsOff,
+    /// Delete the last sentence.
+    Scratch,
+}
+
+struct Command {
+    phrase: &'static str,
+    action: Action,
+    /// The phrase is also an everyday word ("the trial period", "colon
+    /// cancer", "a new line of products"), so it only counts as a command
+    /// when set off from the surrounding speech: at the end of the
+    /// dictation, or next to punctuation Whisper put around it.
+    set_off: bool,
+}
+
+const fn cmd(phrase: &'static str, action: Action) -> Command {
+    Command {
+        phrase,
+        action,
+        set_off: false,
+    }
+}
+
+const fn word(phrase: &'static str, action: Action) -> Command {
+    Command {
+        phrase,
+        action,
+        set_off: true,
+    }
+}
+
+const ENGLISH: &[Command] = &[
+    cmd("new paragraph", Action::Break("\n\n")),
+    word("new line", Action::Break("\n")),
+    cmd("newline", Action::Break("\n")),
+    cmd("comma", Action::Punctuation(",")),
+    word("period", Action::Punctuation(".")),
+    word("full stop", Action::Punctuation(".")),
+    cmd("question mark", Action::Punctuation("?")),
+    cmd("exclamation mark", Action::Punctuation("!")),
+    cmd("exclamation point", Action::Punctuation("!")),
+    word("colon", Action::Punctuation(":")),
+    cmd("semicolon", Action::Punctuation(";")),
+    cmd("ellipsis", Action::Punctuation("...")),
+    cmd("open quote", Action::OpenQuote("\"")),
+    cmd("close quote", Action::CloseQuote("\"")),
+    cmd("end quote", Action::CloseQuote("\"")),
+    cmd("unquote", Action::CloseQuote("\"")),
+    cmd("open paren", Action::OpenQuote("(")),
+    cmd("open parenthesis", Action::OpenQuote("(")),
+    cmd("close paren", Action::CloseQuote(")")),
+    cmd("close parenthesis", Action::CloseQuote(")")),
+    cmd("all caps", Action::CapsOn),
+    cmd("end caps", Action::CapsOff),
+    cmd("scratch that", Action::Scratch),
+];
+
+const CHINESE: &[Command] = &[
+    cmd("新段落", Action::Break("\n\n")),
+    cmd("另起一段", Action::Break("\n\n")),
+    cmd("换行", Action::Break("\n")),
+    cmd("逗号", Action::Punctuation("，")),
+    cmd("句号", Action::Punctuation("。")),
+    cmd("问号", Action::Punctuation("？")),
+    cmd("感叹号", Action::Punctuation("！")),
+    cmd("冒号", Action::Punctuation("：")),
+    cmd("分号", Action::Punctuation("；")),
+    cmd("顿号", Action::Punctuation("、")),
+    cmd("省略号", Action::Punctuation("……")),
+    cmd("左引号", Action::OpenQuote("“")),
+    cmd("前引号", Action::OpenQuote("“")),
+    cmd("右引号", Action::CloseQuote("”")),
+    cmd("后引号", Action::CloseQuote("”")),
+    cmd("左括号", Action::OpenQuote("（")),
+    cmd("右括号", Action::CloseQuote("）")),
+    cmd("全部大写", Action::CapsOn),
+    cmd("结束大写", Action::CapsOff),
+    cmd("删掉这句", Action::Scratch),
+    cmd("删除上一句", Action::Scratch),
+];
+
+/// Command tables for a language code or name ("en", "English", "zh",
+/// "Chinese", ...). Chinese dictation often mixes in English, so it gets both
+/// tables; an unknown (not pinned or detected) language gets both as well.
+/// Other languages have no commands.
+fn tables_for(language: Option<&str>) -> Vec<&'static [Command]> {
+    let language = language.map(|l| l.trim().to_lowercase());
+    match language.as_deref() {
+        Some("en" | "english") => vec![ENGLISH],
+        Some("zh" | "chinese" | "mandarin") => vec![CHINESE, ENGLISH],
+        None | Some("") => vec![ENGLISH, CHINESE],
+        Some(_) => Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Word,
+    Punct,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    kind: Kind,
+}
+
+/// Split into words (letter/digit runs, or single CJK characters), single
+/// punctuation characters and whitespace runs.
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = if c.is_whitespace() {
+            Kind::Space
+        } else if is_cjk(c) && c.is_alphanumeric() {
+            tokens.push(Token {
+                text: &text[start..start + c.len_utf8()],
+                kind: Kind::Word,
+            });
+            continue;
+        } else if is_word_char(c) {
+            Kind::Word
+        } else {
+            Kind::Punct
+        };
+
+        let mut end = start + c.len_utf8();
+        if kind != Kind::Punct {
+            while let Some(&(i, next)) = chars.peek() {
+                let same = match kind {
+                    Kind::Space => next.is_whitespace(),
+                    _ => is_word_char(next) && !is_cjk(next),
+                };
+                if !same {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(Token {
+            text: &text[start..end],
+            kind,
+        });
+    }
+    tokens
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+/// Whisper punctuation that a spoken punctuation command replaces.
+fn is_auto_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ',' | ';' | ':' | '!' | '?' | '。' | '，' | '；' | '：' | '！' | '？' | '、'
+    )
+}
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n')
+}
+
+fn is_opening(c: char) -> bool {
+    matches!(c, '(' | '[' | '{' | '“' | '‘' | '（' | '「' | '《')
+}
+
+/// Replace spoken commands in `text` with the punctuation or formatting they
+/// stand for. `language` selects the command tables (see [`tables_for`]).
+pub fn interpret(text: &str, language: Option<&str>) -> String {
+    let tables = tables_for(language);
+    let mut commands: Vec<(Vec<String>, &Command)> = tables
+        .iter()
+        .flat_map(|table| table.iter())
+        .map(|c| {
+            let words = tokenize(c.phrase)
+                .into_iter()
+                .filter(|t| t.kind == Kind::Word)
+                .map(|t| t.text.to_lowercase())
+                .collect();
+            (words, c)
+        })
+        .collect();
+    // Longest phrases first, so "new paragraph" isn't read as "new" + ...
+    commands.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+
+    let tokens = tokenize(text);
+    let mut out = Writer::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        if token.kind == Kind::Word {
+            if let Some((end, action)) = match_command(&tokens, i, &commands) {
+                out.command(action);
+                i = end;
+                // Drop punctuation Whisper attached to the command itself.
+                while tokens
+                    .get(i)
+                    .is_some_and(|t| t.kind == Kind::Punct && is_auto_punctuation_token(t))
+                {
+                    i += 1;
+                }
+                continue;
+            }
+        }
+        match token.kind {
+            Kind::Word => out.word(token.text),
+            Kind::Punct => out.punct(token.text),
+            Kind::Space => out.space(),
+        }
+        i += 1;
+    }
+    out.finish()
+}
+
+fn is_auto_punctuation_token(token: &Token) -> bool {
+    token.text.chars().all(is_auto_punctuation)
+}
+
+/// If a command starts at word token `start`, return the index just past its
+/// last word and its action. Spaces and punctuation between the command's
+/// words are ignored.
+fn match_command(
+    tokens: &[Token],
+    start: usize,
+    commands: &[(Vec<String>, &Command)],
+) -> Option<(usize, Action)> {
+    'commands: for (words, command) in commands {
+        let mut i = start;
+        for (n, word) in words.iter().enumerate() {
+            if n > 0 {
+                while tokens.get(i).is_some_and(|t| t.kind != Kind::Word) {
+                    i += 1;
+                }
+            }
+            match tokens.get(i) {
+                Some(t) if t.kind == Kind::Word && t.text.to_lowercase() == *word => i += 1,
+                _ => continue 'commands,
+            }
+        }
+        if command.set_off && !is_set_off(tokens, start, i) {
+            continue;
+        }
+        return Some((i, command.action));
+    }
+    None
+}
+
+/// Whether the words `tokens[start..end]` follow a pause (punctuation right
+/// before them), end the text, or are followed by a comma, colon or
+/// semicolon (spaces aside). Whisper ends nearly every sentence with a
+/// period, so sentence-final punctuation after the words doesn't count: "a
+/// quiet period." is just a sentence.
+fn is_set_off(tokens: &[Token], start: usize, end: usize) -> bool {
+    let before = tokens[..start].iter().rev().find(|t| t.kind != Kind::Space);
+    let after = tokens[end..].iter().find(|t| t.kind != Kind::Space);
+    before.is_some_and(|t| t.kind == Kind::Punct)
+        || after.is_none_or(|t| {
+            t.kind == Kind::Punct
+                && is_auto_punctuation_token(t)
+                && !t.text.chars().any(is_sentence_end)
+        })
+}
+
+/// Builds the output, tracking spacing and capitalization state.
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// Whisper had a space here; emitted before the next word if appropriate.
+    pending_space: bool,
+    /// The next word follows an opening quote/bracket and gets no space.
+    glue_next: bool,
+    capitalize_next: bool,
+    caps: bool,
+}
+
+impl Writer {
+    fn space(&mut self) {
+        self.pending_space = true;
+    }
+
+    fn separate(&mut self) {
+        let ends_open = self.out.ends_with(char::is_whitespace) || self.out.is_empty();
+        if self.pending_space && !self.glue_next && !ends_open {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+        self.glue_next = false;
+    }
+
+    fn word(&mut self, word: &str) {
+        self.separate();
+        let mut word = if self.caps {
+            word.to_uppercase()
+        } else {
+            word.to_string()
+        };
+        if self.capitalize_next {
+            word = capitalize(&word);
+            self.capitalize_next = false;
+        }
+        self.out.push_str(&word);
+    }
+
+    fn punct(&mut self, punct: &str) {
+        let opening = punct.chars().all(is_opening)
+            || (punct == "\"" && self.pending_space && !self.out.is_empty());
+        if opening {
+            self.separate();
+            self.out.push_str(punct);
+            self.glue_next = true;
+        } else {
+            self.pending_space = false;
+            self.out.push_str(punct);
+        }
+    }
+
+    fn command(&mut self, action: Action) {
+        match action {
+            Action::Punctuation(p) => {
+                self.trim_end(true);
+                self.out.push_str(p);
+                self.pending_space = false;
+                self.capitalize_next = p.chars().last().is_some_and(is_sentence_end) && p != "...";
+            }
+            Action::Break(b) => {
+                self.trim_end(false);
+                self.out.push_str(b);
+                self.pending_space = false;
+                self.capitalize_next = true;
+            }
+            Action::OpenQuote(q) => {
+                // CJK quotes take no space before them.
+                if q.chars().any(is_cjk) {
+                    self.pending_space = false;
+                }
+                self.separate();
+                self.out.push_str(q);
+                self.glue_next = true;
+            }
+            Action::CloseQuote(q) => {
+                self.trim_end(false);
+                self.out.push_str(q);
+                self.pending_space = false;
+            }
+            Action::CapsOn => self.caps = true,
+            Action::CapsOff => self.caps = false,
+            Action::Scratch => {
+                self.scratch_sentence();
+                self.pending_space = true;
+                self.glue_next = false;
+            }
+        }
+    }
+
+    /// Remove trailing spaces (not line breaks), and with `punctuation` also
+    /// Whisper's trailing punctuation.
+    fn trim_end(&mut self, punctuation: bool) {
+        let trimmed = self.out.trim_end_matches(|c: char| {
+            (c.is_whitespace() && c != '\n') || (punctuation && is_auto_punctuation(c))
+        });
+        self.out.truncate(trimmed.len());
+    }
+
+    /// Delete back to the end of the previous sentence (or line).
+    fn scratch_sentence(&mut self) {
+        self.trim_end(false);
+        if let Some(c) = self
+            .out
+            .chars()
+            .last()
+            .filter(|&c| is_sentence_end(c) && c != '\n')
+        {
+            self.out.pop();
+            // "...": drop the whole run of terminators.
+            while self.out.ends_with(c) {
+                self.out.pop();
+            }
+        }
+        let keep = self
+            .out
+            .rfind(is_sentence_end)
+            .map(|i| i + self.out[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        self.out.truncate(keep);
+        self.trim_end(false);
+    }
+
+    fn finish(self) -> String {
+        self.out
+            .trim_end_matches(|c: char| c.is_whitespace() && c != '\n')
+            .to_string()
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn en(text: &str) -> String {
+        interpret(text, Some("en"))
+    }
+
+    #[test]
+    fn punctuation_replaces_whisper_punctuation() {
+        assert_eq!(en("Hello comma world period"), "Hello, world.");
+        assert_eq!(en("Hello, comma, world. Period."), "Hello, world.");
+        assert_eq!(en("Is it done question mark"), "Is it done?");
+        assert_eq!(en("Wait exclamation point really"), "Wait! Really");
+    }
+
+    #[test]
+    fn line_breaks_drop_spaces_and_capitalize() {
+        assert_eq!(
+            en("Thanks. New line. see you tomorrow"),
+            "Thanks.\nSee you tomorrow"
+        );
+        assert_eq!(en("Intro new paragraph body"), "Intro\n\nBody");
+    }
+
+    #[test]
+    fn quotes_and_caps() {
+        assert_eq!(
+            en("He said open quote hi there close quote and left"),
+            "He said \"hi there\" and left"
+        );
+        assert_eq!(
+            en("This is all caps very important end caps okay"),
+            "This is VERY IMPORTANT okay"
+        );
+    }
+
+    #[test]
+    fn scratch_that_deletes_the_last_sentence() {
+        assert_eq!(
+            en("Hello there. I hate this scratch that I love it"),
+            "Hello there. I love it"
+        );
+        assert_eq!(en("Hello there. Scratch that. Goodbye"), "Goodbye");
+        assert_eq!(en("First. Second line. Scratch that."), "First.");
+    }
+
+    #[test]
+    fn chinese_commands_without_spaces() {
+        assert_eq!(
+            interpret("你好逗号世界句号换行第二行", Some("zh")),
+            "你好，世界。\n第二行"
+        );
+        assert_eq!(
+            interpret("他说左引号好的右引号", Some("Chinese")),
+            "他说“好的”"
+        );
+        // Mixed-language dictation also gets the English table.
+        assert_eq!(
+            interpret("你好 new paragraph world", Some("zh")),
+            "你好\n\nWorld"
+        );
+    }
+
+    #[test]
+    fn locale_limits_the_command_table() {
+        assert_eq!(en("中文逗号"), "中文逗号");
+        assert_eq!(
+            interpret("plain text stays put", None),
+            "plain text stays put"
+        );
+        assert_eq!(en("The commander said so"), "The commander said so");
+        assert_eq!(
+            interpret("Das ist ein Komma, period.", Some("de")),
+            "Das ist ein Komma, period."
+        );
+    }
+
+    #[test]
+    fn everyday_words_are_left_alone() {
+        for text in [
+            "The trial period ends Friday.",
+            "Colon cancer screening starts at 45.",
+            "We launched a new line of products.",
+            "It was a full stop on hiring for the year.",
+            // Whisper's sentence-final punctuation doesn't set them off.
+            "We are in a quiet period.",
+            "The label ends with a colon.",
+            "We launched a new line.",
+            "Hiring came to a full stop!",
+        ] {
+            assert_eq!(en(text), text);
+            assert_eq!(interpret(text, None), text);
+        }
+    }
+
+    #[test]
+    fn everyday_words_work_as_commands_when_set_off() {
+        assert_eq!(en("The trial ends Friday period"), "The trial ends Friday.");
+        assert_eq!(
+            en("Agenda, colon. Budget and hiring"),
+            "Agenda: Budget and hiring"
+        );
+        assert_eq!(en("Done. New line. next"), "Done.\nNext");
+        assert_eq!(en("Ends Friday period, then"), "Ends Friday. Then");
+    }
+}
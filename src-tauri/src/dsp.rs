@@ -0,0 +1,233 @@
+//! Signal processing between capture and upload.
+//!
+//! Whisper works on 16 kHz mono internally, so anything more is wasted upload
+//! size. Devices usually capture at 44.1/48 kHz stereo; this module downmixes
+//! and resamples with a windowed-sinc low-pass filter to avoid aliasing.
+
+/// Sample rate Whisper expects.
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Zero crossings of the sinc kernel on each side of the centre.
+/// More = sharper cutoff, more CPU.
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+
+/// Place the cutoff slightly below the target Nyquist frequency so the
+/// transition band doesn't fold back into the audible range.
+const ROLLOFF: f64 = 0.94;
+
+/// Above this many filter phases, compute kernels on the fly instead of
+/// precomputing a table (only hit for unusual rate pairs).
+const MAX_PRECOMPUTED_PHASES: u64 = 1024;
+
+/// Convert interleaved 16-bit PCM at any rate/channel count to 16 kHz mono.
+pub fn to_whisper_format(samples: &[i16], sample_rate: u32, channels: u16) -> Vec<i16> {
+    let mono = downmix_to_mono(samples, channels);
+    let resampled = resample(&mono, sample_rate, WHISPER_SAMPLE_RATE);
+    resampled
+        .into_iter()
+        .map(|s| s.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+        .collect()
+}
+
+/// Average interleaved channels into one. Trailing partial frames are dropped.
+pub fn downmix_to_mono(samples: &[i16], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| s as f32).sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Resample a mono signal from `from_rate` to `to_rate` Hz.
+///
+/// Uses a Blackman-windowed sinc low-pass filter whose cutoff sits just below
+/// the lower of the two Nyquist frequencies. Kernels are precomputed per
+/// fractional phase (e.g. a single phase for 48 kHz → 16 kHz).
+pub fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 || input.is_empty() {
+        return input.to_vec();
+    }
+
+    // Output sample n sits at input position n * step / phases.
+    let g = gcd(from_rate, to_rate);
+    let step = (from_rate / g) as u64;
+    let phases = (to_rate / g) as u64;
+
+    let band = (to_rate as f64 / from_rate as f64).min(1.0) * ROLLOFF;
+    let half_width = (SINC_ZERO_CROSSINGS / band).ceil() as i64;
+
+    let table: Option<Vec<Vec<f32>>> = (phases <= MAX_PRECOMPUTED_PHASES).then(|| {
+        (0..phases)
+            .map(|p| sinc_kernel(p as f64 / phases as f64, band, half_width))
+            .collect()
+    });
+
+    let out_len = (input.len() as u64 * phases / step) as usize;
+    let mut output = Vec::with_capacity(out_len);
+
+    for n in 0..out_len as u64 {
+        let pos = n * step;
+        let base = (pos / phases) as i64;
+        let phase = pos % phases;
+
+        let computed;
+        let taps = match &table {
+            Some(table) => &table[phase as usize],
+            None => {
+                computed = sinc_kernel(phase as f64 / phases as f64, band, half_width);
+                &computed
+            }
+        };
+
+        // Taps cover input indices base - half_width + 1 ..= base + half_width.
+        let first = base - half_width + 1;
+        let mut acc = 0.0f32;
+        for (i, &w) in taps.iter().enumerate() {
+            let idx = first + i as i64;
+            if idx >= 0 && (idx as usize) < input.len() {
+                acc += input[idx as usize] * w;
+            }
+        }
+        output.push(acc);
+    }
+
+    output
+}
+
+/// Filter taps for an output sample `frac` (0..1) input samples past an
+/// input sample, normalized to unity DC gain.
+fn sinc_kernel(frac: f64, band: f64, half_width: i64) -> Vec<f32> {
+    let taps: Vec<f64> = (-half_width + 1..=half_width)
+        .map(|k| {
+            let x = k as f64 - frac;
+            band * sinc(band * x) * blackman(x / half_width as f64)
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.into_iter().map(|t| (t / sum) as f32).collect()
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// Blackman window over u ∈ [-1, 1] (zero outside).
+fn blackman(u: f64) -> f64 {
+    if u.abs() >= 1.0 {
+        return 0.0;
+    }
+    let a = std::f64::consts::PI * u;
+    0.42 + 0.5 * a.cos() + 0.08 * (2.0 * a).cos()
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sine(freq: f32, rate: u32, len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn downmix_averages_channels() {
+        let stereo = [100i16, 300, -200, 200, 7, 7];
+        assert_eq!(downmix_to_mono(&stereo, 2), vec![200.0, 0.0, 7.0]);
+    }
+
+    #[test]
+    fn downmix_mono_is_identity() {
+        let mono = [1i16, -2, 3];
+        assert_eq!(downmix_to_mono(&mono, 1), vec![1.0, -2.0, 3.0]);
+    }
+
+    #[test]
+    fn downmix_drops_partial_frame() {
+        assert_eq!(downmix_to_mono(&[1, 1, 1], 2).len(), 1);
+    }
+
+    #[test]
+    fn resample_same_rate_is_identity() {
+        let input = sine(440.0, 16_000, 1000, 1000.0);
+        assert_eq!(resample(&input, 16_000, 16_000), input);
+    }
+
+    #[test]
+    fn resample_output_length_matches_duration() {
+        let one_second_48k = vec![0.0; 48_000];
+        assert_eq!(resample(&one_second_48k, 48_000, 16_000).len(), 16_000);
+
+        let one_second_44k = vec![0.0; 44_100];
+        assert_eq!(resample(&one_second_44k, 44_100, 16_000).len(), 16_000);
+
+        let half_second_8k = vec![0.0; 4_000];
+        assert_eq!(resample(&half_second_8k, 8_000, 16_000).len(), 8_000);
+    }
+
+    #[test]
+    fn resample_preserves_in_band_tone() {
+        for from in [48_000, 44_100, 22_050] {
+            let input = sine(440.0, from, from as usize, 10_000.0);
+            let output = resample(&input, from, 16_000);
+            let expected = sine(440.0, 16_000, output.len(), 10_000.0);
+
+            // Ignore filter edges.
+            let mid = 200..output.len() - 200;
+            let max_err = output[mid.clone()]
+                .iter()
+                .zip(&expected[mid])
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0f32, f32::max);
+            assert!(max_err < 50.0, "{from} Hz: max error {max_err} of 10000");
+        }
+    }
+
+    #[test]
+    fn resample_removes_content_above_target_nyquist() {
+        // 12 kHz would alias to 4 kHz at 16 kHz without the low-pass filter.
+        let input = sine(12_000.0, 48_000, 48_000, 10_000.0);
+        let output = resample(&input, 48_000, 16_000);
+        let mid = &output[200..output.len() - 200];
+        assert!(rms(mid) < 10.0, "aliased energy: rms {}", rms(mid));
+    }
+
+    #[test]
+    fn resample_handles_odd_rate_pairs() {
+        // gcd(44_056, 16_000) = 8 → 2000 phases, exercises the on-the-fly path.
+        let input = sine(300.0, 44_056, 44_056, 10_000.0);
+        let output = resample(&input, 44_056, 16_000);
+        assert_eq!(output.len(), 16_000);
+        let ratio = rms(&output[200..15_800]) / rms(&input);
+        assert!((ratio - 1.0).abs() < 0.01, "rms ratio {ratio}");
+    }
+
+    #[test]
+    fn to_whisper_format_produces_16k_mono() {
+        let stereo: Vec<i16> = sine(440.0, 48_000, 48_000, 8_000.0)
+            .into_iter()
+            .flat_map(|s| [s as i16, s as i16])
+            .collect();
+        let out = to_whisper_format(&stereo, 48_000, 2);
+        assert_eq!(out.len(), 16_000);
+        let peak = out[200..15_800].iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!((7_900..=8_100).contains(&peak), "peak {peak}");
+    }
+}
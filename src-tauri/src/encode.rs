@@ -0,0 +1,814 @@
+//! Upload encoding for transcription requests.
+//!
+//! Recordings are stored as 16 kHz mono WAV, but uploads can be re-encoded as
+//! FLAC (lossless, roughly half the size) or Ogg/Opus (lossy, ~10x smaller,
+//! requires the `opus` cargo feature). Before uploading, [`prepare_upload`]
+//! checks the payload against the provider's size limit and falls back to a
+//! smaller encoding, or splits the audio, instead of running into HTTP 413.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::chunking;
+use crate::dsp;
+use crate::http_client;
+
+/// Upload encoding selected in settings (`upload_encoding`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadEncoding {
+    Wav,
+    Flac,
+    Opus,
+}
+
+/// All encodings, largest output first. Fallbacks walk this list forward.
+const ENCODINGS_BY_SIZE: [UploadEncoding; 3] =
+    [UploadEncoding::Wav, UploadEncoding::Flac, UploadEncoding::Opus];
+
+/// Opus bitrate for speech at 16 kHz. Also used by the size estimate, so it
+/// exists without the `opus` feature.
+#[cfg_attr(not(feature = "opus"), allow(dead_code))]
+const OPUS_BITRATE: i32 = 24_000;
+
+/// Aim split parts at this fraction of the limit to absorb estimate error.
+const SPLIT_HEADROOM: f64 = 0.9;
+
+/// Never split into parts shorter than this.
+const MIN_PART_SECS: usize = 5;
+
+impl UploadEncoding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "wav" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            "opus" | "ogg" => Some(Self::Opus),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Opus => "opus",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Opus => "ogg",
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            Self::Wav => "audio/wav",
+            Self::Flac => "audio/flac",
+            Self::Opus => "audio/ogg",
+        }
+    }
+
+    /// Whether this build can produce the encoding.
+    pub fn is_available(self) -> bool {
+        self != Self::Opus || cfg!(feature = "opus")
+    }
+
+    /// Encodings this build supports, as settings strings.
+    pub fn available() -> Vec<&'static str> {
+        ENCODINGS_BY_SIZE
+            .iter()
+            .filter(|e| e.is_available())
+            .map(|e| e.as_str())
+            .collect()
+    }
+
+    /// Rough payload size for `frames` mono samples, used for the preflight.
+    fn estimate_bytes(self, frames: usize, sample_rate: u32) -> u64 {
+        let frames = frames as u64;
+        match self {
+            // Exact: header + 16-bit samples.
+            Self::Wav => 44 + frames * 2,
+            // Speech typically compresses to 50-60% of PCM.
+            Self::Flac => 42 + frames * 2 * 6 / 10,
+            // Constant bitrate plus ~5% Ogg overhead.
+            Self::Opus => {
+                let secs = frames as f64 / sample_rate.max(1) as f64;
+                (secs * OPUS_BITRATE as f64 / 8.0 * 1.05) as u64 + 1024
+            }
+        }
+    }
+
+    fn encode(self, samples: &[i16], sample_rate: u32) -> Result<Vec<u8>> {
+        match self {
+            Self::Wav => encode_wav(samples, sample_rate),
+            Self::Flac => Ok(flac::encode(samples, sample_rate)),
+            #[cfg(feature = "opus")]
+            Self::Opus => opus::encode(samples, sample_rate),
+            #[cfg(not(feature = "opus"))]
+            Self::Opus => Err(anyhow!(
+                "Opus encoding is not available in this build (enable the `opus` feature)"
+            )),
+        }
+    }
+}
+
+/// One request's worth of encoded audio.
+#[derive(Debug)]
+pub struct EncodedAudio {
+    pub bytes: Vec<u8>,
+    pub file_name: String,
+    pub mime: &'static str,
+}
+
+/// Encode `samples` as `preferred` and make sure every payload fits in
+/// `max_bytes`. `stem` names the uploaded file(s).
+///
+/// If the preferred encoding is (or is estimated to be) too large, smaller
+/// encodings are tried in order. If even the smallest one doesn't fit, the
+/// audio is split at quiet points into parts that are transcribed separately.
+pub fn prepare_upload(
+    samples: &[i16],
+    sample_rate: u32,
+    stem: &str,
+    preferred: UploadEncoding,
+    max_bytes: u64,
+) -> Result<Vec<EncodedAudio>> {
+    let start = ENCODINGS_BY_SIZE
+        .iter()
+        .position(|&e| e == preferred)
+        .unwrap_or(0);
+    let candidates: Vec<UploadEncoding> = ENCODINGS_BY_SIZE[start..]
+        .iter()
+        .copied()
+        .filter(|e| e.is_available())
+        .collect();
+    let smallest = *candidates
+        .last()
+        .ok_or_else(|| anyhow!("Upload encoding '{}' is not available", preferred.as_str()))?;
+
+    for &encoding in &candidates {
+        let estimate = encoding.estimate_bytes(samples.len(), sample_rate);
+        if estimate > max_bytes {
+            http_client::log_warning(&format!(
+                "Upload: {} payload estimated at {} exceeds the {} limit; trying a smaller encoding",
+                encoding.as_str(),
+                format_size(estimate),
+                format_size(max_bytes)
+            ));
+            continue;
+        }
+
+        let bytes = encoding.encode(samples, sample_rate)?;
+        if bytes.len() as u64 <= max_bytes {
+            if encoding != preferred {
+                http_client::log_warning(&format!(
+                    "Upload: using {} ({}) instead of {} to stay under the {} limit",
+                    encoding.as_str(),
+                    format_size(bytes.len() as u64),
+                    preferred.as_str(),
+                    format_size(max_bytes)
+                ));
+            }
+            return Ok(vec![EncodedAudio {
+                bytes,
+                file_name: format!("{stem}.{}", encoding.extension()),
+                mime: encoding.mime(),
+            }]);
+        }
+    }
+
+    split_and_encode(samples, sample_rate, smallest, max_bytes, stem)
+}
+
+/// Encode `samples` as several payloads that each fit in `max_bytes`.
+fn split_and_encode(
+    samples: &[i16],
+    sample_rate: u32,
+    encoding: UploadEncoding,
+    max_bytes: u64,
+    stem: &str,
+) -> Result<Vec<EncodedAudio>> {
+    let estimate = encoding.estimate_bytes(samples.len(), sample_rate).max(1) as f64;
+    let min_len = sample_rate as usize * MIN_PART_SECS;
+    let mut max_len =
+        (samples.len() as f64 * max_bytes as f64 * SPLIT_HEADROOM / estimate) as usize;
+
+    while max_len >= min_len {
+        let segments = chunking::split_at_silence(samples, sample_rate, max_len, 0);
+        let encoded = segments
+            .iter()
+            .map(|s| encoding.encode(&samples[s.range.clone()], sample_rate))
+            .collect::<Result<Vec<_>>>()?;
+
+        if encoded.iter().all(|b| b.len() as u64 <= max_bytes) {
+            http_client::log_warning(&format!(
+                "Upload: audio is too large for one request ({} limit); splitting into {} parts",
+                format_size(max_bytes),
+                encoded.len()
+            ));
+            return Ok(encoded
+                .into_iter()
+                .enumerate()
+                .map(|(i, bytes)| EncodedAudio {
+                    bytes,
+                    file_name: format!("{stem}-part{}.{}", i + 1, encoding.extension()),
+                    mime: encoding.mime(),
+                })
+                .collect());
+        }
+        max_len /= 2;
+    }
+
+    Err(anyhow!(
+        "Recording is too large to upload: even {}s parts exceed the {} limit",
+        MIN_PART_SECS,
+        format_size(max_bytes)
+    ))
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Read a WAV file as 16 kHz mono, converting older recordings if needed.
+pub fn read_wav_mono(path: &Path) -> Result<(Vec<i16>, u32)> {
+    let mut reader = hound::WavReader::open(path)
+        .with_context(|| format!("Failed to read audio file: {}", path.display()))?;
+    let spec = reader.spec();
+    if spec.bits_per_sample != 16 || spec.sample_format != hound::SampleFormat::Int {
+        return Err(anyhow!("Unsupported WAV format in {}", path.display()));
+    }
+    let samples = reader
+        .samples::<i16>()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to decode WAV samples")?;
+
+    if spec.channels == 1 && spec.sample_rate == dsp::WHISPER_SAMPLE_RATE {
+        Ok((samples, spec.sample_rate))
+    } else {
+        Ok((
+            dsp::to_whisper_format(&samples, spec.sample_rate, spec.channels),
+            dsp::WHISPER_SAMPLE_RATE,
+        ))
+    }
+}
+
+fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec).context("Failed to create wav")?;
+    for &s in samples {
+        writer.write_sample(s).context("Failed to write wav")?;
+    }
+    writer.finalize().context("Failed to finalize wav")?;
+    Ok(cursor.into_inner())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// FLAC
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Minimal FLAC encoder for 16-bit mono: fixed predictors (orders 0-4) with
+/// partitioned Rice coding. Speech compresses to roughly half of PCM size.
+mod flac {
+    const BLOCK_SIZE: usize = 4096;
+    const BITS_PER_SAMPLE: u32 = 16;
+    const MAX_FIXED_ORDER: usize = 4;
+    const MAX_PARTITION_ORDER: u32 = 8;
+    /// Rice parameters 0-14; 15 is the escape code.
+    const MAX_RICE_PARAM: u32 = 14;
+
+    pub fn encode(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(samples.len());
+        out.extend_from_slice(b"fLaC");
+        write_streaminfo(&mut out, samples.len() as u64, sample_rate);
+
+        for (frame_number, block) in samples.chunks(BLOCK_SIZE).enumerate() {
+            let block: Vec<i32> = block.iter().map(|&s| s as i32).collect();
+            write_frame(&mut out, frame_number as u64, &block);
+        }
+        out
+    }
+
+    fn write_streaminfo(out: &mut Vec<u8>, total_samples: u64, sample_rate: u32) {
+        // Last-metadata-block flag + type 0 (STREAMINFO), length 34.
+        out.extend_from_slice(&[0x80, 0, 0, 34]);
+
+        let mut w = BitWriter::default();
+        w.write(BLOCK_SIZE as u64, 16); // min block size
+        w.write(BLOCK_SIZE as u64, 16); // max block size
+        w.write(0, 24); // min frame size (unknown)
+        w.write(0, 24); // max frame size (unknown)
+        w.write(sample_rate as u64, 20);
+        w.write(0, 3); // channels - 1
+        w.write((BITS_PER_SAMPLE - 1) as u64, 5);
+        w.write(total_samples, 36);
+        out.extend_from_slice(&w.into_bytes());
+        out.extend_from_slice(&[0; 16]); // MD5 (unknown)
+    }
+
+    fn write_frame(out: &mut Vec<u8>, frame_number: u64, block: &[i32]) {
+        let mut w = BitWriter::default();
+
+        // Frame header.
+        w.write(0b11_1111_1111_1110, 14); // sync
+        w.write(0, 1); // reserved
+        w.write(0, 1); // fixed block size
+        let partial = block.len() != BLOCK_SIZE;
+        w.write(if partial { 0b0111 } else { 0b1100 }, 4); // 16-bit size at end / 4096
+        w.write(0b0000, 4); // sample rate from STREAMINFO
+        w.write(0b0000, 4); // mono
+        w.write(0b100, 3); // 16 bits per sample
+        w.write(0, 1); // reserved
+        write_utf8_number(&mut w, frame_number);
+        if partial {
+            w.write(block.len() as u64 - 1, 16);
+        }
+        let crc = crc8(w.bytes());
+        w.write(crc as u64, 8);
+
+        write_subframe(&mut w, block);
+
+        w.align();
+        let crc = crc16(w.bytes());
+        w.write(crc as u64, 16);
+        out.extend_from_slice(&w.into_bytes());
+    }
+
+    fn write_subframe(w: &mut BitWriter, block: &[i32]) {
+        if block.iter().all(|&s| s == block[0]) {
+            w.write(0, 1);
+            w.write(0b000000, 6); // CONSTANT
+            w.write(0, 1);
+            write_signed(w, block[0], BITS_PER_SAMPLE);
+            return;
+        }
+
+        let verbatim_bits = block.len() as u64 * BITS_PER_SAMPLE as u64;
+        let best = (0..=MAX_FIXED_ORDER.min(block.len() - 1))
+            .map(|order| {
+                let residual = fixed_residual(block, order);
+                let (bits, partition_order, params) = best_partitioning(&residual, block.len(), order);
+                let total = bits.saturating_add(order as u64 * BITS_PER_SAMPLE as u64);
+                (total, order, residual, partition_order, params)
+            })
+            .min_by_key(|(total, ..)| *total);
+
+        match best {
+            Some((total, order, residual, partition_order, params)) if total < verbatim_bits => {
+                w.write(0, 1);
+                w.write(0b001000 | order as u64, 6); // FIXED, order
+                w.write(0, 1);
+                for &s in &block[..order] {
+                    write_signed(w, s, BITS_PER_SAMPLE);
+                }
+                write_residual(w, &residual, block.len(), order, partition_order, &params);
+            }
+            _ => {
+                w.write(0, 1);
+                w.write(0b000001, 6); // VERBATIM
+                w.write(0, 1);
+                for &s in block {
+                    write_signed(w, s, BITS_PER_SAMPLE);
+                }
+            }
+        }
+    }
+
+    /// Residual of the fixed polynomial predictor of `order` (samples after
+    /// the first `order` warm-up samples).
+    fn fixed_residual(block: &[i32], order: usize) -> Vec<i32> {
+        (order..block.len())
+            .map(|i| {
+                let s = |k: usize| block[i - k];
+                match order {
+                    0 => s(0),
+                    1 => s(0) - s(1),
+                    2 => s(0) - 2 * s(1) + s(2),
+                    3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                    _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+                }
+            })
+            .collect()
+    }
+
+    /// Pick the partition order and per-partition Rice parameters that
+    /// minimize the coded size. Returns (bits, partition order, params).
+    fn best_partitioning(residual: &[i32], block_len: usize, order: usize) -> (u64, u32, Vec<u32>) {
+        let folded: Vec<u64> = residual.iter().map(|&r| zigzag(r)).collect();
+        let mut best: Option<(u64, u32, Vec<u32>)> = None;
+
+        for partition_order in 0..=MAX_PARTITION_ORDER {
+            let partitions = 1usize << partition_order;
+            if !block_len.is_multiple_of(partitions) || block_len / partitions <= order {
+                break;
+            }
+            let mut bits = 2 + 4; // coding method + partition order
+            let mut params = Vec::with_capacity(partitions);
+            for range in partition_ranges(block_len, order, partition_order) {
+                let (cost, param) = best_rice_param(&folded[range]);
+                bits += 4 + cost;
+                params.push(param);
+            }
+            if best.as_ref().is_none_or(|(b, _, _)| bits < *b) {
+                best = Some((bits, partition_order, params));
+            }
+        }
+        best.unwrap_or((u64::MAX, 0, Vec::new()))
+    }
+
+    /// Residual index ranges of each partition. The first partition is
+    /// shorter by `order` because warm-up samples aren't coded as residual.
+    fn partition_ranges(
+        block_len: usize,
+        order: usize,
+        partition_order: u32,
+    ) -> impl Iterator<Item = std::ops::Range<usize>> {
+        let partition_len = block_len >> partition_order;
+        (0..1usize << partition_order).map(move |p| {
+            let start = (p * partition_len).saturating_sub(order);
+            let end = (p + 1) * partition_len - order;
+            start..end
+        })
+    }
+
+    fn best_rice_param(folded: &[u64]) -> (u64, u32) {
+        (0..=MAX_RICE_PARAM)
+            .map(|k| {
+                let bits: u64 = folded.iter().map(|&u| (u >> k) + 1 + k as u64).sum();
+                (bits, k)
+            })
+            .min()
+            .unwrap_or((0, 0))
+    }
+
+    fn write_residual(
+        w: &mut BitWriter,
+        residual: &[i32],
+        block_len: usize,
+        order: usize,
+        partition_order: u32,
+        params: &[u32],
+    ) {
+        w.write(0b00, 2); // Rice, 4-bit parameters
+        w.write(partition_order as u64, 4);
+        for (range, &k) in partition_ranges(block_len, order, partition_order).zip(params) {
+            w.write(k as u64, 4);
+            for &r in &residual[range] {
+                let u = zigzag(r);
+                w.write_unary(u >> k);
+                w.write(u & ((1 << k) - 1), k);
+            }
+        }
+    }
+
+    fn zigzag(r: i32) -> u64 {
+        ((r << 1) ^ (r >> 31)) as u32 as u64
+    }
+
+    fn write_signed(w: &mut BitWriter, value: i32, bits: u32) {
+        w.write((value as u32 as u64) & ((1 << bits) - 1), bits);
+    }
+
+    /// FLAC's UTF-8-style variable-length frame number.
+    fn write_utf8_number(w: &mut BitWriter, n: u64) {
+        if n < 0x80 {
+            w.write(n, 8);
+            return;
+        }
+        let continuation = match n {
+            0..=0x7ff => 1,
+            0x800..=0xffff => 2,
+            0x1_0000..=0x1f_ffff => 3,
+            0x20_0000..=0x3ff_ffff => 4,
+            0x400_0000..=0x7fff_ffff => 5,
+            _ => 6,
+        };
+        let lead_marker = (0xff00u64 >> (continuation + 1)) & 0xff;
+        w.write(lead_marker | (n >> (6 * continuation)), 8);
+        for i in (0..continuation).rev() {
+            w.write(0x80 | ((n >> (6 * i)) & 0x3f), 8);
+        }
+    }
+
+    fn crc8(data: &[u8]) -> u8 {
+        data.iter().fold(0u8, |mut crc, &b| {
+            crc ^= b;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+            }
+            crc
+        })
+    }
+
+    fn crc16(data: &[u8]) -> u16 {
+        data.iter().fold(0u16, |mut crc, &b| {
+            crc ^= (b as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+            }
+            crc
+        })
+    }
+
+    /// MSB-first bit writer.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        acc: u64,
+        nbits: u32,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u64, bits: u32) {
+            if bits == 0 {
+                return;
+            }
+            // Flush in ≤32-bit pieces so `acc` never overflows.
+            if bits > 32 {
+                self.write(value >> 32, bits - 32);
+                self.write(value & 0xffff_ffff, 32);
+                return;
+            }
+            self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+            self.nbits += bits;
+            while self.nbits >= 8 {
+                self.nbits -= 8;
+                self.bytes.push((self.acc >> self.nbits) as u8);
+            }
+            self.acc &= (1u64 << self.nbits) - 1;
+        }
+
+        /// `n` zero bits followed by a one.
+        fn write_unary(&mut self, mut n: u64) {
+            while n >= 32 {
+                self.write(0, 32);
+                n -= 32;
+            }
+            self.write(1, n as u32 + 1);
+        }
+
+        fn align(&mut self) {
+            if self.nbits > 0 {
+                self.write(0, 8 - self.nbits);
+            }
+        }
+
+        /// Completed bytes (call after `align` for the whole stream).
+        fn bytes(&self) -> &[u8] {
+            &self.bytes
+        }
+
+        fn into_bytes(mut self) -> Vec<u8> {
+            self.align();
+            self.bytes
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Ogg/Opus
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(feature = "opus")]
+mod opus {
+    use super::OPUS_BITRATE;
+    use anyhow::{anyhow, Context, Result};
+    use audiopus::coder::Encoder;
+    use audiopus::{Application, Bitrate, Channels, SampleRate};
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+    /// 20 ms frames.
+    const FRAME_MS: usize = 20;
+    /// Recommended maximum packet size from the Opus docs.
+    const MAX_PACKET: usize = 4000;
+    /// Ogg granule positions are always in 48 kHz samples.
+    const GRANULE_RATE: u64 = 48_000;
+    const SERIAL: u32 = 0x6772_6f71;
+
+    pub fn encode(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>> {
+        let rate = SampleRate::try_from(sample_rate as i32)
+            .map_err(|_| anyhow!("Opus does not support {sample_rate} Hz"))?;
+        let mut encoder = Encoder::new(rate, Channels::Mono, Application::Voip)
+            .context("Failed to create Opus encoder")?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(OPUS_BITRATE))
+            .context("Failed to set Opus bitrate")?;
+
+        let scale = GRANULE_RATE / sample_rate as u64;
+        let pre_skip = encoder.lookahead().context("Failed to query Opus lookahead")? as u64 * scale;
+
+        let mut writer = PacketWriter::new(Vec::new());
+        writer.write_packet(opus_head(pre_skip as u16, sample_rate), SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+        writer.write_packet(opus_tags(), SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+        let frame_len = sample_rate as usize * FRAME_MS / 1000;
+        let frame_count = samples.len().div_ceil(frame_len).max(1);
+        let mut frame = vec![0i16; frame_len];
+        let mut packet = vec![0u8; MAX_PACKET];
+
+        for i in 0..frame_count {
+            let start = i * frame_len;
+            let end = (start + frame_len).min(samples.len());
+            frame.fill(0);
+            frame[..end - start].copy_from_slice(&samples[start..end]);
+
+            let len = encoder
+                .encode(&frame, &mut packet)
+                .context("Opus encoding failed")?;
+
+            let last = i + 1 == frame_count;
+            // The final granule marks the true end so decoders trim the padding.
+            let granule = pre_skip + end as u64 * scale;
+            let info = if last {
+                PacketWriteEndInfo::EndStream
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            writer.write_packet(packet[..len].to_vec().into_boxed_slice(), SERIAL, info, granule)?;
+        }
+
+        Ok(writer.into_inner())
+    }
+
+    /// `OpusHead` identification header (RFC 7845 §5.1).
+    fn opus_head(pre_skip: u16, input_rate: u32) -> Box<[u8]> {
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1); // version
+        head.push(1); // channels
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&input_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // mapping family
+        head.into_boxed_slice()
+    }
+
+    /// `OpusTags` comment header (RFC 7845 §5.2).
+    fn opus_tags() -> Box<[u8]> {
+        let vendor = b"groqtranscriber";
+        let mut tags = Vec::with_capacity(16 + vendor.len());
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor);
+        tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+        tags.into_boxed_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Speech-like test audio: 1.5s tone bursts with light noise, separated
+    /// by 0.5s of near-silence, so there are quiet points to split at.
+    fn speechlike(secs: usize) -> Vec<i16> {
+        let mut seed = 0x2545_f491_u32;
+        let mut noise = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            ((seed >> 16) % 64) as i32 - 32
+        };
+        (0..secs * RATE as usize)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let voiced = (i / (RATE as usize / 2)) % 4 != 3;
+                let tone = if voiced {
+                    ((t * 220.0 * std::f32::consts::TAU).sin() * 6000.0
+                        + (t * 530.0 * std::f32::consts::TAU).sin() * 2000.0) as i32
+                } else {
+                    0
+                };
+                (tone + noise()) as i16
+            })
+            .collect()
+    }
+
+    fn decode_flac(bytes: &[u8]) -> (claxon::metadata::StreamInfo, Vec<i16>) {
+        let mut reader = claxon::FlacReader::new(Cursor::new(bytes)).expect("valid FLAC stream");
+        let info = reader.streaminfo();
+        let samples = reader
+            .samples()
+            .map(|s| s.expect("valid FLAC frame") as i16)
+            .collect();
+        (info, samples)
+    }
+
+    #[test]
+    fn flac_round_trips_losslessly() {
+        let mut samples = speechlike(3);
+        // A constant block, a full-scale block that only codes verbatim,
+        // and a partial final block.
+        samples.extend(std::iter::repeat_n(-7, 4096));
+        samples.extend((0..4096).map(|i| if i % 2 == 0 { i16::MAX } else { i16::MIN }));
+        samples.extend((0..1000).map(|i| (i * 37 % 2001 - 1000) as i16));
+
+        let bytes = flac::encode(&samples, RATE);
+        let (info, decoded) = decode_flac(&bytes);
+        assert_eq!(info.sample_rate, RATE);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.samples, Some(samples.len() as u64));
+        assert_eq!((info.min_block_size, info.max_block_size), (4096, 4096));
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn flac_compresses_speechlike_audio() {
+        let samples = speechlike(4);
+        let flac = flac::encode(&samples, RATE).len() as u64;
+        let wav = encode_wav(&samples, RATE).unwrap().len() as u64;
+        assert!(flac < wav * 6 / 10, "flac {flac} vs wav {wav}");
+        assert!(flac <= UploadEncoding::Flac.estimate_bytes(samples.len(), RATE));
+    }
+
+    #[test]
+    fn size_estimates() {
+        let samples = speechlike(2);
+        let wav = encode_wav(&samples, RATE).unwrap();
+        assert_eq!(
+            UploadEncoding::Wav.estimate_bytes(samples.len(), RATE),
+            wav.len() as u64
+        );
+        let ten_secs = 10 * RATE as usize;
+        let expected = (10.0 * OPUS_BITRATE as f64 / 8.0 * 1.05) as u64 + 1024;
+        assert_eq!(
+            UploadEncoding::Opus.estimate_bytes(ten_secs, RATE),
+            expected
+        );
+    }
+
+    #[test]
+    fn prepare_upload_keeps_the_preferred_encoding_when_it_fits() {
+        let samples = speechlike(2);
+        let parts = prepare_upload(&samples, RATE, "rec", UploadEncoding::Wav, u64::MAX).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].file_name, "rec.wav");
+        assert_eq!(parts[0].mime, "audio/wav");
+    }
+
+    #[test]
+    fn prepare_upload_falls_back_to_flac() {
+        let samples = speechlike(4);
+        let wav_size = UploadEncoding::Wav.estimate_bytes(samples.len(), RATE);
+        let limit = wav_size * 8 / 10;
+        let parts = prepare_upload(&samples, RATE, "rec", UploadEncoding::Wav, limit).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].file_name, "rec.flac");
+        assert!(parts[0].bytes.len() as u64 <= limit);
+        assert_eq!(decode_flac(&parts[0].bytes).1, samples);
+    }
+
+    #[test]
+    fn prepare_upload_splits_when_nothing_fits() {
+        let samples = speechlike(20);
+        let flac_size = flac::encode(&samples, RATE).len() as u64;
+        let limit = flac_size / 3;
+        let parts = prepare_upload(&samples, RATE, "rec", UploadEncoding::Wav, limit).unwrap();
+
+        if cfg!(feature = "opus") {
+            // Opus is ~10x smaller than FLAC, so it fits without splitting.
+            assert_eq!(parts.len(), 1);
+            assert_eq!(parts[0].file_name, "rec.ogg");
+            return;
+        }
+        assert!(parts.len() >= 3, "{} parts", parts.len());
+        let mut rejoined = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            assert_eq!(part.file_name, format!("rec-part{}.flac", i + 1));
+            assert!(part.bytes.len() as u64 <= limit);
+            rejoined.extend(decode_flac(&part.bytes).1);
+        }
+        assert_eq!(rejoined, samples);
+    }
+
+    #[test]
+    fn split_and_encode_keeps_every_part_under_the_limit() {
+        let samples = speechlike(60);
+        for limit in [600_000, 400_000, 250_000] {
+            let parts =
+                split_and_encode(&samples, RATE, UploadEncoding::Wav, limit, "rec").unwrap();
+            assert!(parts.len() > 1);
+            assert!(parts.iter().all(|p| p.bytes.len() as u64 <= limit));
+            let total: usize = parts.iter().map(|p| (p.bytes.len() - 44) / 2).sum();
+            assert_eq!(total, samples.len());
+        }
+    }
+
+    #[test]
+    fn split_and_encode_gives_up_below_the_minimum_part_length() {
+        let samples = speechlike(20);
+        let limit = UploadEncoding::Wav.estimate_bytes(RATE as usize, RATE);
+        assert!(split_and_encode(&samples, RATE, UploadEncoding::Wav, limit, "rec").is_err());
+    }
+}
@@ -0,0 +1,429 @@
+//! Custom vocabulary: product names and jargon Whisper tends to misspell.
+//!
+//! The glossary is used twice. Its terms are appended to the Whisper `prompt`
+//! (within the prompt's token limit) so Whisper is more likely to spell them
+//! right, and whatever it still gets wrong is fixed afterwards by replacing
+//! known sound-alike variants with the canonical term.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::chunking;
+
+/// Whisper only looks at the last 224 tokens of the prompt.
+pub const MAX_PROMPT_TOKENS: usize = 224;
+
+/// Compiled size allowed for the replacement pattern. A case-insensitive
+/// alternation of a few thousand imported spellings outgrows regex's 10 MB
+/// default.
+const PATTERN_SIZE_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Separates variants inside the CSV `variants` column.
+const CSV_VARIANT_SEPARATOR: char = ';';
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    /// Canonical spelling, e.g. "GroqBara".
+    pub term: String,
+    /// Misspellings to replace with `term`, e.g. "grok bara".
+    #[serde(default)]
+    pub variants: Vec<String>,
+}
+
+impl GlossaryEntry {
+    /// Trim the term and variants, dropping empty and duplicate variants.
+    /// Returns `None` if the term is empty.
+    pub fn cleaned(self) -> Option<Self> {
+        let term = self.term.trim().to_string();
+        if term.is_empty() {
+            return None;
+        }
+        let mut variants: Vec<String> = Vec::new();
+        for variant in self.variants {
+            let variant = variant.trim();
+            if !variant.is_empty()
+                && normalize(variant) != normalize(&term)
+                && !variants.iter().any(|v| normalize(v) == normalize(variant))
+            {
+                variants.push(variant.to_string());
+            }
+        }
+        Some(Self { term, variants })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Whisper prompt
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Combine the configured prompt with the glossary terms, staying within
+/// [`MAX_PROMPT_TOKENS`].
+///
+/// The configured prompt comes first and is kept whole when it fits (if it
+/// alone is too long, only its end is kept, as Whisper would). Terms fill the
+/// remaining budget in glossary order; those that don't fit are left out.
+pub fn build_prompt(prompt: Option<&str>, entries: &[GlossaryEntry]) -> Option<String> {
+    let prompt = prompt.map(str::trim).filter(|p| !p.is_empty());
+    let mut out = prompt
+        .map(|p| keep_tail(p, MAX_PROMPT_TOKENS))
+        .unwrap_or_default();
+    let mut budget = MAX_PROMPT_TOKENS.saturating_sub(estimate_tokens(&out));
+
+    let mut terms = Vec::new();
+    for entry in entries {
+        // Each term also costs a separator token.
+        let cost = estimate_tokens(&entry.term) + 1;
+        if cost > budget {
+            break;
+        }
+        budget -= cost;
+        terms.push(entry.term.as_str());
+    }
+
+    if !terms.is_empty() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(&terms.join(", "));
+        out.push('.');
+    }
+    Some(out).filter(|p| !p.is_empty())
+}
+
+/// Conservative estimate of Whisper's token count for `text`.
+///
+/// Whisper uses a byte-level BPE: common English words are one token and
+/// longer ones about four characters per token, while CJK and other
+/// non-ASCII characters often take two tokens each.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|word| {
+            let ascii = word.chars().filter(char::is_ascii).count();
+            let other = word.chars().count() - ascii;
+            ascii.div_ceil(4).max(usize::from(ascii > 0)) + 2 * other
+        })
+        .sum()
+}
+
+/// The longest run of trailing words of `text` that fits in `max_tokens`.
+fn keep_tail(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for word in text.split_whitespace().rev() {
+        used += estimate_tokens(word);
+        if used > max_tokens {
+            break;
+        }
+        kept.push(word);
+    }
+    kept.reverse();
+    kept.join(" ")
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Replacement pass
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Compiled replacement pass for a glossary.
+pub struct Replacer {
+    pattern: Option<Regex>,
+    /// Normalized variant (or term) -> canonical term.
+    terms: HashMap<String, String>,
+}
+
+impl Replacer {
+    /// Compile the replacement pass. Fails only if the glossary is too large
+    /// to compile into one pattern.
+    pub fn new(entries: &[GlossaryEntry]) -> Result<Self> {
+        let mut terms = HashMap::new();
+        for entry in entries {
+            // The term itself is included so wrong casing gets fixed too.
+            for spelling in std::iter::once(&entry.term).chain(&entry.variants) {
+                let key = normalize(spelling);
+                if !key.is_empty() {
+                    // The first entry wins if two entries claim the same spelling.
+                    terms.entry(key).or_insert_with(|| entry.term.clone());
+                }
+            }
+        }
+
+        // Longest spellings first, so "grok bara pro" wins over "grok bara".
+        let mut keys: Vec<&String> = terms.keys().collect();
+        keys.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+        let alternatives: Vec<String> = keys.iter().map(|k| spelling_pattern(k)).collect();
+
+        let pattern = if alternatives.is_empty() {
+            None
+        } else {
+            let pattern = RegexBuilder::new(&alternatives.join("|"))
+                .case_insensitive(true)
+                .size_limit(PATTERN_SIZE_LIMIT)
+                .build()
+                .with_context(|| {
+                    format!(
+                        "Glossary is too large to compile ({} spellings)",
+                        terms.len()
+                    )
+                })?;
+            Some(pattern)
+        };
+        Ok(Self { pattern, terms })
+    }
+
+    /// Replace every glossary spelling in `text` with its canonical term.
+    /// Returns the new text and the number of replacements that changed it.
+    pub fn apply(&self, text: &str) -> (String, usize) {
+        let Some(pattern) = &self.pattern else {
+            return (text.to_string(), 0);
+        };
+
+        let mut out = String::with_capacity(text.len());
+        let mut changed = 0;
+        let mut copied = 0;
+        let mut pos = 0;
+        while let Some(m) = pattern.find_at(text, pos) {
+            let term = self.terms.get(&normalize(m.as_str()));
+            let Some(term) = term.filter(|_| at_word_boundary(text, m.start(), m.end())) else {
+                // Retry just past the start of the rejected match.
+                pos = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+                continue;
+            };
+            out.push_str(&text[copied..m.start()]);
+            out.push_str(term);
+            if m.as_str() != term {
+                changed += 1;
+            }
+            copied = m.end();
+            pos = m.end();
+        }
+        out.push_str(&text[copied..]);
+        (out, changed)
+    }
+}
+
+/// Regex for a normalized spelling: separators match any run of spaces or
+/// hyphens, so "grok bara" also catches "Grok-Bara".
+fn spelling_pattern(normalized: &str) -> String {
+    normalized
+        .split(' ')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(r"[\s\-]+")
+}
+
+/// Lowercase, with runs of whitespace and hyphens collapsed to one space.
+fn normalize(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A match must not continue a word on either side. CJK text has no spaces,
+/// so CJK characters don't count as word characters.
+fn at_word_boundary(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() && !chunking::is_cjk(c);
+    let first = text[start..end].chars().next();
+    let last = text[start..end].chars().next_back();
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+
+    let left_ok = !first.is_some_and(is_word) || !before.is_some_and(is_word);
+    let right_ok = !last.is_some_and(is_word) || !after.is_some_and(is_word);
+    left_ok && right_ok
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// CSV import/export
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Serialize as CSV with a `term,variants` header; variants are joined with `;`.
+pub fn to_csv(entries: &[GlossaryEntry]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["term", "variants"])?;
+    for entry in entries {
+        let variants = entry.variants.join(&format!("{CSV_VARIANT_SEPARATOR} "));
+        writer.write_record([entry.term.as_str(), variants.as_str()])?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| anyhow!("Failed to write CSV: {e}"))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Parse CSV produced by [`to_csv`] or written by hand.
+///
+/// The first column is the term. Variants may be `;`-separated in the second
+/// column or spread over further columns. A `term` header row and blank
+/// lines are skipped.
+pub fn from_csv(text: &str) -> Result<Vec<GlossaryEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let mut entries = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Invalid CSV on line {}", i + 1))?;
+        let Some(term) = record.get(0) else {
+            continue;
+        };
+        if i == 0 && term.eq_ignore_ascii_case("term") {
+            continue;
+        }
+        let variants = record
+            .iter()
+            .skip(1)
+            .flat_map(|field| field.split(CSV_VARIANT_SEPARATOR))
+            .map(str::to_string)
+            .collect();
+        if let Some(entry) = (GlossaryEntry {
+            term: term.to_string(),
+            variants,
+        })
+        .cleaned()
+        {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(term: &str, variants: &[&str]) -> GlossaryEntry {
+        GlossaryEntry {
+            term: term.to_string(),
+            variants: variants.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    fn glossary() -> Vec<GlossaryEntry> {
+        vec![
+            entry("GroqBara", &["grok bara", "grokbara", "groq bar"]),
+            entry("Kubernetes", &["cooper netties"]),
+            entry("Qwen", &["quen", "q when"]),
+        ]
+    }
+
+    #[test]
+    fn replaces_variants_and_fixes_casing() {
+        let replacer = Replacer::new(&glossary()).unwrap();
+        let (text, changed) =
+            replacer.apply("Deploy grok-bara to Cooper Netties, then ask groqbara and QWEN.");
+        assert_eq!(
+            text,
+            "Deploy GroqBara to Kubernetes, then ask GroqBara and Qwen."
+        );
+        assert_eq!(changed, 4);
+    }
+
+    #[test]
+    fn respects_word_boundaries() {
+        let replacer = Replacer::new(&glossary()).unwrap();
+        let input = "The queen said quench, not quen-ish or unquenched.";
+        let (text, _) = replacer.apply(input);
+        assert_eq!(text, "The queen said quench, not Qwen-ish or unquenched.");
+        assert_eq!(replacer.apply("grokbaras").0, "grokbaras");
+    }
+
+    #[test]
+    fn matches_inside_cjk_text() {
+        let replacer =
+            Replacer::new(&[entry("GroqBara", &["grok bara"]), entry("千问", &["千文"])]).unwrap();
+        assert_eq!(
+            replacer.apply("我们用grok bara和千文").0,
+            "我们用GroqBara和千问"
+        );
+    }
+
+    #[test]
+    fn prefers_longest_spelling_and_leaves_terms_alone() {
+        let replacer = Replacer::new(&[
+            entry("GroqBara", &["groq bar"]),
+            entry("GroqBara Pro", &["groq bar pro"]),
+        ])
+        .unwrap();
+        assert_eq!(replacer.apply("groq bar pro").0, "GroqBara Pro");
+        assert_eq!(
+            replacer.apply("GroqBara is fine"),
+            ("GroqBara is fine".to_string(), 0)
+        );
+        assert_eq!(
+            Replacer::new(&[]).unwrap().apply("unchanged").0,
+            "unchanged"
+        );
+    }
+
+    #[test]
+    fn prompt_stays_within_token_limit() {
+        let prompt = build_prompt(Some("Meeting notes about our stack."), &glossary()).unwrap();
+        assert_eq!(
+            prompt,
+            "Meeting notes about our stack. GroqBara, Kubernetes, Qwen."
+        );
+        assert_eq!(build_prompt(None, &[]), None);
+        assert_eq!(build_prompt(Some("  "), &[]), None);
+
+        let many: Vec<GlossaryEntry> = (0..500).map(|i| entry(&format!("Term{i}"), &[])).collect();
+        let prompt = build_prompt(Some("Context."), &many).unwrap();
+        assert!(
+            estimate_tokens(&prompt) <= MAX_PROMPT_TOKENS + 1,
+            "{}",
+            estimate_tokens(&prompt)
+        );
+        assert!(prompt.starts_with("Context. Term0, Term1"));
+
+        let long = "word ".repeat(400);
+        let prompt = build_prompt(Some(&long), &glossary()).unwrap();
+        assert!(estimate_tokens(&prompt) <= MAX_PROMPT_TOKENS);
+    }
+
+    #[test]
+    fn csv_round_trip_and_hand_written_input() {
+        let entries = vec![
+            entry("GroqBara", &["grok bara", "grokbara"]),
+            entry("Acme, Inc.", &[]),
+        ];
+        let csv = to_csv(&entries).unwrap();
+        assert_eq!(from_csv(&csv).unwrap(), entries);
+
+        let hand = "Kubernetes, cooper netties, kuber nettis\n\n  Qwen ,quen;q when\n,orphan\n";
+        assert_eq!(
+            from_csv(hand).unwrap(),
+            vec![
+                entry("Kubernetes", &["cooper netties", "kuber nettis"]),
+                entry("Qwen", &["quen", "q when"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn large_imported_glossary_compiles() {
+        let entries: Vec<GlossaryEntry> = (0..4000)
+            .map(|i| {
+                let term = format!("Produktname{i}");
+                let variants = [format!("produkt name {i}"), format!("prodükt nahme {i}")];
+                GlossaryEntry {
+                    term,
+                    variants: variants.to_vec(),
+                }
+            })
+            .collect();
+        let replacer = Replacer::new(&entries).unwrap();
+        assert_eq!(
+            replacer.apply("ship prodükt nahme 3999 today").0,
+            "ship Produktname3999 today"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Filter for Whisper hallucinations on empty or near-silent audio.
+//!
+//! Whisper tends to "hear" stock phrases ("Thanks for watching!", "you") in
+//! silence. A transcript is treated as a hallucination when any of these hold:
+//! - the recording never gets loud enough to contain speech (RMS statistics)
+//! - every segment of a verbose response fails Whisper's own no-speech test
+//!   (`no_speech_prob` high and `avg_logprob` low)
+//! - the whole transcript is a blocklisted phrase, unless the verbose
+//!   response is confident that the audio contains speech
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::encode;
+use crate::transcribe::Transcript;
+
+/// Phrases Whisper commonly produces for silence or background noise.
+/// Matched against the whole transcript, ignoring case and punctuation.
+pub const DEFAULT_BLOCKLIST: &[&str] = &[
+    "you",
+    "thank you",
+    "thanks",
+    "thank you very much",
+    "thank you so much",
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "thanks for listening",
+    "please subscribe",
+    "like and subscribe",
+    "subtitles by the amara.org community",
+    "bye",
+    "字幕由amara.org社区提供",
+    "谢谢观看",
+    "谢谢大家",
+    "ご視聴ありがとうございました",
+];
+
+/// Loudest 100 ms window must reach this RMS (16-bit scale, about -44 dBFS)
+/// for the recording to possibly contain speech.
+const MIN_SPEECH_RMS: f64 = 200.0;
+const LOUDNESS_WINDOW_MS: usize = 100;
+
+/// Whisper's own thresholds for treating a segment as silence.
+const NO_SPEECH_PROB_THRESHOLD: f32 = 0.6;
+const LOGPROB_THRESHOLD: f32 = -1.0;
+
+/// Below this `no_speech_prob` on every segment, a blocklisted phrase is
+/// assumed to have actually been said.
+const CONFIDENT_SPEECH_PROB: f32 = 0.1;
+
+/// Loudness summary of a recording.
+#[derive(Debug, Clone, Copy)]
+pub struct AudioStats {
+    pub rms: f64,
+    /// RMS of the loudest `LOUDNESS_WINDOW_MS` window.
+    pub peak_window_rms: f64,
+}
+
+impl AudioStats {
+    pub fn from_wav(path: &Path) -> Result<Self> {
+        let (samples, sample_rate) = encode::read_wav_mono(path)?;
+        Ok(Self::from_samples(&samples, sample_rate))
+    }
+
+    pub fn from_samples(samples: &[i16], sample_rate: u32) -> Self {
+        let window = (sample_rate as usize * LOUDNESS_WINDOW_MS / 1000).max(1);
+        let peak_window_rms = samples
+            .chunks(window)
+            .map(rms)
+            .fold(0.0, f64::max);
+        Self {
+            rms: rms(samples),
+            peak_window_rms,
+        }
+    }
+}
+
+fn rms(samples: &[i16]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / samples.len() as f64).sqrt()
+}
+
+/// Why `transcript` looks like a hallucination, or `None` if it should be kept.
+///
+/// `audio` is optional so a transcript can still be checked when the
+/// recording can't be read.
+pub fn check(transcript: &Transcript, audio: Option<&AudioStats>, blocklist: &[String]) -> Option<String> {
+    let text = transcript.text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if let Some(audio) = audio {
+        if audio.peak_window_rms < MIN_SPEECH_RMS {
+            return Some(format!(
+                "audio is near-silent (peak RMS {:.0}, overall {:.0})",
+                audio.peak_window_rms, audio.rms
+            ));
+        }
+    }
+
+    let segments = &transcript.segments;
+    let no_speech_probs: Vec<f32> = segments.iter().filter_map(|s| s.no_speech_prob).collect();
+
+    if !segments.is_empty()
+        && segments.iter().all(|s| {
+            s.no_speech_prob.is_some_and(|p| p > NO_SPEECH_PROB_THRESHOLD)
+                && s.avg_logprob.is_none_or(|l| l < LOGPROB_THRESHOLD)
+        })
+    {
+        let max = no_speech_probs.iter().copied().fold(0.0f32, f32::max);
+        return Some(format!("Whisper reports no speech (no_speech_prob {max:.2})"));
+    }
+
+    let normalized = normalize(text);
+    if let Some(phrase) = blocklist.iter().find(|p| normalize(p) == normalized) {
+        let confident_speech = !no_speech_probs.is_empty()
+            && no_speech_probs.len() == segments.len()
+            && no_speech_probs.iter().all(|&p| p < CONFIDENT_SPEECH_PROB);
+        if !confident_speech {
+            return Some(format!("matches known hallucination phrase \"{phrase}\""));
+        }
+    }
+
+    None
+}
+
+/// Lowercase, drop punctuation and collapse whitespace.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '.')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .map(|word| word.trim_matches('.').to_string())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::TranscriptSegment;
+
+    fn blocklist() -> Vec<String> {
+        DEFAULT_BLOCKLIST.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn loud() -> AudioStats {
+        AudioStats {
+            rms: 2000.0,
+            peak_window_rms: 6000.0,
+        }
+    }
+
+    fn segment(no_speech_prob: f32, avg_logprob: f32) -> TranscriptSegment {
+        TranscriptSegment {
+            no_speech_prob: Some(no_speech_prob),
+            avg_logprob: Some(avg_logprob),
+        }
+    }
+
+    fn transcript(text: &str, segments: Vec<TranscriptSegment>) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            language: None,
+            segments,
+        }
+    }
+
+    #[test]
+    fn keeps_normal_speech() {
+        let t = transcript("Let's meet at noon tomorrow.", vec![segment(0.02, -0.2)]);
+        assert_eq!(check(&t, Some(&loud()), &blocklist()), None);
+    }
+
+    #[test]
+    fn drops_text_on_near_silent_audio() {
+        let quiet = AudioStats::from_samples(&vec![30; 16_000], 16_000);
+        let t = transcript("Let's meet at noon tomorrow.", Vec::new());
+        assert!(check(&t, Some(&quiet), &blocklist()).unwrap().contains("near-silent"));
+    }
+
+    #[test]
+    fn drops_when_every_segment_is_no_speech() {
+        let t = transcript("Okay.", vec![segment(0.9, -1.4), segment(0.7, -1.1)]);
+        assert!(check(&t, Some(&loud()), &blocklist()).unwrap().contains("no_speech_prob"));
+
+        let mixed = transcript("Okay. Real words.", vec![segment(0.9, -1.4), segment(0.05, -0.3)]);
+        assert_eq!(check(&mixed, Some(&loud()), &blocklist()), None);
+    }
+
+    #[test]
+    fn drops_blocklisted_phrases_ignoring_case_and_punctuation() {
+        for text in ["Thanks for watching!", "  you ", "THANK YOU.", "字幕由Amara.org社区提供"] {
+            let t = transcript(text, Vec::new());
+            assert!(check(&t, Some(&loud()), &blocklist()).is_some(), "{text}");
+        }
+        let t = transcript("Thank you for the review", Vec::new());
+        assert_eq!(check(&t, Some(&loud()), &blocklist()), None);
+    }
+
+    #[test]
+    fn keeps_blocklisted_phrase_when_speech_is_confident() {
+        let t = transcript("Thank you.", vec![segment(0.01, -0.1)]);
+        assert_eq!(check(&t, Some(&loud()), &blocklist()), None);
+    }
+
+    #[test]
+    fn custom_blocklist_replaces_defaults() {
+        let t = transcript("Thanks for watching!", Vec::new());
+        assert_eq!(check(&t, None, &["beep".to_string()]), None);
+        let t = transcript("Beep.", Vec::new());
+        assert!(check(&t, None, &["beep".to_string()]).is_some());
+    }
+}
@@ -0,0 +1,342 @@
+//! Transcription history persisted as JSON in the app data dir.
+//!
+//! Every pipeline result is appended here so past dictations can be listed,
+//! searched, re-copied or re-typed from the UI. Recordings are moved into
+//! `recordings/` next to `history.json` so they can be re-transcribed or
+//! re-refined later; each re-run is kept as a [`HistoryVariant`].
+
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf, sync::Mutex};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Serializes read-modify-write cycles on `history.json`.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    /// Unique, monotonically increasing id (milliseconds since epoch at creation).
+    pub id: u64,
+
+    /// RFC 3339 timestamp of when the transcription finished.
+    pub timestamp: String,
+
+    /// Transcript as returned by the transcription provider, with glossary
+    /// corrections applied.
+    pub raw_text: String,
+
+    /// Refined transcript, if refinement was enabled and succeeded.
+    #[serde(default)]
+    pub refined_text: Option<String>,
+
+    /// Transcription model (or local command) that produced `raw_text`.
+    pub model: String,
+
+    /// Refinement model that produced `refined_text`.
+    #[serde(default)]
+    pub refinement_model: Option<String>,
+
+    /// Length of the recording in milliseconds.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+
+    /// Path to the recorded WAV file.
+    #[serde(default)]
+    pub wav_path: Option<String>,
+
+    /// Why transcription failed, if it did. The recording is still kept so it
+    /// can be re-transcribed.
+    #[serde(default)]
+    pub error: Option<String>,
+
+    /// Spoken language as reported by the transcription provider.
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Whether `raw_text` is an English translation of the recording rather
+    /// than a transcript; `language` is then the source language.
+    #[serde(default)]
+    pub translated: bool,
+
+    /// Re-transcriptions and re-refinements, oldest first.
+    #[serde(default)]
+    pub variants: Vec<HistoryVariant>,
+}
+
+impl HistoryEntry {
+    /// The most recent text for this entry: the latest variant if any,
+    /// otherwise the refined (or raw) transcript.
+    pub fn final_text(&self) -> &str {
+        self.variants
+            .last()
+            .map(|v| v.text.as_str())
+            .or(self.refined_text.as_deref())
+            .unwrap_or(&self.raw_text)
+    }
+
+    /// The most recent transcription: the latest "transcription" variant if
+    /// any, otherwise `raw_text`.
+    pub fn latest_transcript(&self) -> &str {
+        self.variants
+            .iter()
+            .rev()
+            .find(|v| v.kind == VARIANT_TRANSCRIPTION)
+            .map(|v| v.text.as_str())
+            .unwrap_or(&self.raw_text)
+    }
+}
+
+pub const VARIANT_TRANSCRIPTION: &str = "transcription";
+pub const VARIANT_REFINEMENT: &str = "refinement";
+
+/// An alternative result produced by re-running part of the pipeline on a
+/// stored recording.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryVariant {
+    /// RFC 3339 timestamp of when the variant was produced.
+    pub timestamp: String,
+
+    /// "transcription" or "refinement".
+    pub kind: String,
+
+    pub text: String,
+
+    /// Transcription or refinement model used.
+    pub model: String,
+
+    /// Custom refinement prompt, for refinement variants.
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+/// Fields of a new entry; `id` and `timestamp` are assigned on insert.
+pub struct NewEntry {
+    pub raw_text: String,
+    pub refined_text: Option<String>,
+    pub model: String,
+    pub refinement_model: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub wav_path: Option<PathBuf>,
+    pub error: Option<String>,
+    pub language: Option<String>,
+    pub translated: bool,
+}
+
+fn history_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app_data_dir: {e}"))?;
+    Ok(dir.join("history.json"))
+}
+
+fn recordings_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app_data_dir: {e}"))?;
+    Ok(dir.join("recordings"))
+}
+
+/// Move a freshly recorded WAV out of the temp dir into `recordings/`, under
+/// a name no other entry uses.
+pub fn store_recording<R: Runtime>(app: &AppHandle<R>, wav_path: &Path) -> Result<PathBuf, String> {
+    let dir = recordings_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create recordings dir: {e}"))?;
+
+    let dest = reserve_recording_path(&dir)?;
+
+    // `rename` fails across filesystems (temp dir is often tmpfs), so fall back to copy.
+    if fs::rename(wav_path, &dest).is_err() {
+        fs::copy(wav_path, &dest).map_err(|e| format!("Failed to store recording: {e}"))?;
+        let _ = fs::remove_file(wav_path);
+    }
+    Ok(dest)
+}
+
+/// Create an empty, uniquely named file in `dir` for a recording to be moved
+/// onto. Names use a millisecond timestamp plus a counter on collision, and
+/// `create_new` guarantees an existing recording is never overwritten.
+fn reserve_recording_path(dir: &Path) -> Result<PathBuf, String> {
+    let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S%3f");
+    for n in 0..1000 {
+        let name = match n {
+            0 => format!("recording-{stamp}.wav"),
+            n => format!("recording-{stamp}-{n}.wav"),
+        };
+        let path = dir.join(name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create recording file: {e}")),
+        }
+    }
+    Err("Failed to find a free recording file name".into())
+}
+
+fn remove_recording(entry: &HistoryEntry) {
+    if let Some(path) = &entry.wav_path {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn load_unlocked<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<HistoryEntry>, String> {
+    let path = history_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read history: {e}"))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse history: {e}"))
+}
+
+fn save_unlocked<R: Runtime>(app: &AppHandle<R>, entries: &[HistoryEntry]) -> Result<(), String> {
+    let path = history_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create history dir: {e}"))?;
+    }
+    let bytes = serde_json::to_vec_pretty(entries)
+        .map_err(|e| format!("Failed to serialize history: {e}"))?;
+    fs::write(&path, bytes).map_err(|e| format!("Failed to write history: {e}"))
+}
+
+/// Append a new entry and return it.
+pub fn add<R: Runtime>(app: &AppHandle<R>, new: NewEntry) -> Result<HistoryEntry, String> {
+    let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load_unlocked(app)?;
+
+    let now = chrono::Utc::now();
+    let last_id = entries.iter().map(|e| e.id).max().unwrap_or(0);
+    let id = (now.timestamp_millis().max(0) as u64).max(last_id + 1);
+
+    let entry = HistoryEntry {
+        id,
+        timestamp: now.to_rfc3339(),
+        raw_text: new.raw_text,
+        refined_text: new.refined_text,
+        model: new.model,
+        refinement_model: new.refinement_model,
+        duration_ms: new.duration_ms,
+        wav_path: new.wav_path.map(|p| p.to_string_lossy().into_owned()),
+        error: new.error,
+        language: new.language,
+        translated: new.translated,
+        variants: Vec::new(),
+    };
+    entries.push(entry.clone());
+    save_unlocked(app, &entries)?;
+    Ok(entry)
+}
+
+/// All entries, newest first.
+pub fn list<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<HistoryEntry>, String> {
+    let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load_unlocked(app)?;
+    entries.reverse();
+    Ok(entries)
+}
+
+pub fn get<R: Runtime>(app: &AppHandle<R>, id: u64) -> Result<HistoryEntry, String> {
+    list(app)?
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("History entry {id} not found"))
+}
+
+/// Case-insensitive full-text search over raw, refined and variant text.
+///
+/// Every whitespace-separated word in `query` must appear in the entry.
+/// Results are newest first.
+pub fn search<R: Runtime>(app: &AppHandle<R>, query: &str) -> Result<Vec<HistoryEntry>, String> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let entries = list(app)?;
+    if terms.is_empty() {
+        return Ok(entries);
+    }
+
+    Ok(entries
+        .into_iter()
+        .filter(|e| {
+            let mut haystack = format!(
+                "{}\n{}",
+                e.raw_text.to_lowercase(),
+                e.refined_text.as_deref().unwrap_or("").to_lowercase()
+            );
+            for v in &e.variants {
+                haystack.push('\n');
+                haystack.push_str(&v.text.to_lowercase());
+            }
+            terms.iter().all(|t| haystack.contains(t.as_str()))
+        })
+        .collect())
+}
+
+/// Attach a re-run result to an entry and return the updated entry.
+///
+/// If the entry's original transcription failed, the first successful
+/// re-transcription also fills in `raw_text` and clears `error`.
+pub fn add_variant<R: Runtime>(
+    app: &AppHandle<R>,
+    id: u64,
+    variant: HistoryVariant,
+) -> Result<HistoryEntry, String> {
+    let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load_unlocked(app)?;
+    let entry = entries
+        .iter_mut()
+        .find(|e| e.id == id)
+        .ok_or_else(|| format!("History entry {id} not found"))?;
+
+    if variant.kind == VARIANT_TRANSCRIPTION && entry.error.is_some() && entry.raw_text.is_empty() {
+        entry.raw_text = variant.text.clone();
+        entry.model = variant.model.clone();
+        entry.error = None;
+    }
+    entry.variants.push(variant);
+
+    let updated = entry.clone();
+    save_unlocked(app, &entries)?;
+    Ok(updated)
+}
+
+/// Build a variant timestamped now.
+pub fn new_variant(kind: &str, text: String, model: String, prompt: Option<String>) -> HistoryVariant {
+    HistoryVariant {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        kind: kind.to_string(),
+        text,
+        model,
+        prompt,
+    }
+}
+
+/// Remove an entry and its recording. Returns an error if it doesn't exist.
+pub fn delete<R: Runtime>(app: &AppHandle<R>, id: u64) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut entries = load_unlocked(app)?;
+    let Some(pos) = entries.iter().position(|e| e.id == id) else {
+        return Err(format!("History entry {id} not found"));
+    };
+    let removed = entries.remove(pos);
+    save_unlocked(app, &entries)?;
+    remove_recording(&removed);
+    Ok(())
+}
+
+/// Remove all entries and their recordings.
+pub fn clear<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+    let entries = load_unlocked(app)?;
+    save_unlocked(app, &[])?;
+    entries.iter().for_each(remove_recording);
+    Ok(())
+}
+
+/// Length of a WAV file in milliseconds, if it can be read.
+pub fn wav_duration_ms(path: &Path) -> Option<u64> {
+    let reader = hound::WavReader::open(path).ok()?;
+    let spec = reader.spec();
+    if spec.sample_rate == 0 {
+        return None;
+    }
+    // `duration()` is in frames (samples per channel).
+    Some(reader.duration() as u64 * 1000 / spec.sample_rate as u64)
+}
@@ -0,0 +1,338 @@
+//! Shared HTTP client for the Whisper and chat API calls.
+//!
+//! - One `reqwest::Client` for the whole app (connection pooling, TLS reuse)
+//!   with connect and read timeouts.
+//! - Exponential-backoff retries on 5xx responses and connection/timeout errors.
+//! - On 429, waits for `retry-after` / `x-ratelimit-reset-*` before retrying
+//!   and reports the wait through the retry logger (a "warn" log event).
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Retries after the initial attempt.
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// Don't sit on a rate limit longer than this; fail and let the user retry.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(30);
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+type RetryLogger = Box<dyn Fn(&str) + Send + Sync>;
+static RETRY_LOGGER: OnceLock<RetryLogger> = OnceLock::new();
+
+/// The shared client. Built on first use.
+pub fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Install the sink for retry / rate-limit / upload messages (set once at startup).
+pub fn set_retry_logger<F>(logger: F)
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    let _ = RETRY_LOGGER.set(Box::new(logger));
+}
+
+/// Report a recoverable problem (retry, rate limit, upload fallback, lost
+/// input device) through the retry logger.
+pub fn log_warning(message: &str) {
+    match RETRY_LOGGER.get() {
+        Some(logger) => logger(message),
+        None => eprintln!("{message}"),
+    }
+}
+
+/// Send a request, retrying transient failures.
+///
+/// `build` is called once per attempt because request bodies (multipart
+/// forms in particular) can't be cloned. The final response is returned
+/// as-is, so callers still inspect the status for non-retryable errors.
+pub async fn send_with_retry<F>(label: &str, build: F) -> Result<Response>
+where
+    F: Fn() -> Result<RequestBuilder>,
+{
+    let mut attempt = 0;
+    loop {
+        let retries_left = attempt < MAX_RETRIES;
+
+        match build()?.send().await {
+            Ok(res) if res.status() == StatusCode::TOO_MANY_REQUESTS => {
+                let wait = rate_limit_wait(res.headers());
+                let limits = describe_rate_limits(res.headers());
+                let wait = match wait {
+                    // Waits are clamped to the maximum, so reaching it means
+                    // the reset is at least that far off.
+                    Some(w) if w >= MAX_RATE_LIMIT_WAIT => {
+                        log_warning(&format!(
+                            "{label}: rate limited (429), reset in {:.0}s or more{limits}; not retrying",
+                            w.as_secs_f32()
+                        ));
+                        return Ok(res);
+                    }
+                    Some(w) => w,
+                    None => backoff(attempt),
+                };
+                if !retries_left {
+                    return Ok(res);
+                }
+                log_warning(&format!(
+                    "{label}: rate limited (429){limits}; retrying in {:.1}s (attempt {}/{})",
+                    wait.as_secs_f32(),
+                    attempt + 1,
+                    MAX_RETRIES
+                ));
+                tokio::time::sleep(wait).await;
+            }
+            Ok(res) if res.status().is_server_error() && retries_left => {
+                let wait = backoff(attempt);
+                log_warning(&format!(
+                    "{label}: server error {}; retrying in {:.1}s (attempt {}/{})",
+                    res.status(),
+                    wait.as_secs_f32(),
+                    attempt + 1,
+                    MAX_RETRIES
+                ));
+                tokio::time::sleep(wait).await;
+            }
+            Ok(res) => return Ok(res),
+            Err(e) if retries_left && (e.is_connect() || e.is_timeout() || e.is_request()) => {
+                let wait = backoff(attempt);
+                log_warning(&format!(
+                    "{label}: {e}; retrying in {:.1}s (attempt {}/{})",
+                    wait.as_secs_f32(),
+                    attempt + 1,
+                    MAX_RETRIES
+                ));
+                tokio::time::sleep(wait).await;
+            }
+            Err(e) => return Err(e).with_context(|| format!("{label} request failed")),
+        }
+
+        attempt += 1;
+    }
+}
+
+/// 500ms, 1s, 2s, 4s, ... capped at `MAX_BACKOFF`.
+fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(1 << attempt.min(16))
+        .min(MAX_BACKOFF)
+}
+
+/// How long a 429 response asks us to wait, at most `MAX_RATE_LIMIT_WAIT`.
+///
+/// Prefers `retry-after` (seconds or HTTP date). Otherwise uses the reset time
+/// of whichever `x-ratelimit-*` bucket is exhausted, or the soonest reset.
+fn rate_limit_wait(headers: &HeaderMap) -> Option<Duration> {
+    if let Some(v) = header_str(headers, "retry-after") {
+        let v = v.trim();
+        if let Ok(secs) = v.parse::<f64>() {
+            return wait_from_secs(secs);
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(v) {
+            let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+            return wait_from_secs(delta.num_milliseconds() as f64 / 1000.0);
+        }
+    }
+
+    let mut resets = Vec::new();
+    for bucket in ["requests", "tokens"] {
+        let Some(reset) = header_str(headers, &format!("x-ratelimit-reset-{bucket}"))
+            .and_then(parse_reset_duration)
+        else {
+            continue;
+        };
+        let exhausted = header_str(headers, &format!("x-ratelimit-remaining-{bucket}"))
+            .is_some_and(|r| r.trim() == "0");
+        if exhausted {
+            return Some(reset);
+        }
+        resets.push(reset);
+    }
+    resets.into_iter().min()
+}
+
+/// Human-readable summary of the `x-ratelimit-*` headers for log messages.
+fn describe_rate_limits(headers: &HeaderMap) -> String {
+    let mut parts = Vec::new();
+    for bucket in ["requests", "tokens"] {
+        let remaining = header_str(headers, &format!("x-ratelimit-remaining-{bucket}"));
+        let limit = header_str(headers, &format!("x-ratelimit-limit-{bucket}"));
+        if let (Some(remaining), Some(limit)) = (remaining, limit) {
+            parts.push(format!("{remaining}/{limit} {bucket} left"));
+        }
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", parts.join(", "))
+    }
+}
+
+/// A server-supplied wait in seconds as a `Duration`: negative values (a
+/// reset that already passed) become zero, large ones are clamped to
+/// `MAX_RATE_LIMIT_WAIT`, and NaN/infinity are rejected.
+fn wait_from_secs(secs: f64) -> Option<Duration> {
+    if !secs.is_finite() {
+        return None;
+    }
+    let secs = secs.clamp(0.0, MAX_RATE_LIMIT_WAIT.as_secs_f64());
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Parse Go-style durations as sent by Groq/OpenAI, e.g. `"2m59.56s"`,
+/// `"7.66s"`, `"120ms"` or `"1h"`. A bare number is taken as seconds.
+/// The result is clamped like [`wait_from_secs`].
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if let Ok(secs) = value.parse::<f64>() {
+        return wait_from_secs(secs);
+    }
+
+    let mut total = 0.0_f64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let num_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let num: f64 = rest[..num_len].parse().ok()?;
+        rest = &rest[num_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += num * scale;
+    }
+    wait_from_secs(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn retry_after_seconds_and_http_date() {
+        let wait = rate_limit_wait(&headers(&[("retry-after", "7")]));
+        assert_eq!(wait, Some(Duration::from_secs(7)));
+        let wait = rate_limit_wait(&headers(&[("retry-after", "1.5")]));
+        assert_eq!(wait, Some(Duration::from_millis(1500)));
+
+        let soon = chrono::Utc::now() + chrono::Duration::seconds(10);
+        let wait = rate_limit_wait(&headers(&[("retry-after", &soon.to_rfc2822())])).unwrap();
+        assert!(wait > Duration::from_secs(8) && wait <= Duration::from_secs(10));
+        let past = chrono::Utc::now() - chrono::Duration::seconds(10);
+        let wait = rate_limit_wait(&headers(&[("retry-after", &past.to_rfc2822())]));
+        assert_eq!(wait, Some(Duration::ZERO));
+        let wait = rate_limit_wait(&headers(&[(
+            "retry-after",
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+        )]));
+        assert_eq!(wait, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn ratelimit_reset_prefers_exhausted_bucket() {
+        let map = headers(&[
+            ("x-ratelimit-reset-requests", "2m30s"),
+            ("x-ratelimit-remaining-requests", "5"),
+            ("x-ratelimit-reset-tokens", "250ms"),
+            ("x-ratelimit-remaining-tokens", "100"),
+        ]);
+        assert_eq!(rate_limit_wait(&map), Some(Duration::from_millis(250)));
+
+        let map = headers(&[
+            ("x-ratelimit-reset-requests", "12s"),
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-tokens", "250ms"),
+        ]);
+        assert_eq!(rate_limit_wait(&map), Some(Duration::from_secs(12)));
+        assert_eq!(rate_limit_wait(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn parses_go_style_reset_durations() {
+        assert_eq!(parse_reset_duration("0m20s"), Some(Duration::from_secs(20)));
+        // 90s is longer than the maximum wait, so it's clamped.
+        assert_eq!(parse_reset_duration("1m30s"), Some(MAX_RATE_LIMIT_WAIT));
+        assert_eq!(
+            parse_reset_duration("250ms"),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            parse_reset_duration("7.5s"),
+            Some(Duration::from_millis(7500))
+        );
+        assert_eq!(parse_reset_duration(" 3 "), Some(Duration::from_secs(3)));
+        // Long resets are clamped to the maximum wait.
+        assert_eq!(parse_reset_duration("1h"), Some(MAX_RATE_LIMIT_WAIT));
+        assert_eq!(parse_reset_duration("1m"), Some(MAX_RATE_LIMIT_WAIT));
+    }
+
+    #[test]
+    fn negative_waits_become_zero() {
+        assert_eq!(parse_reset_duration("-5"), Some(Duration::ZERO));
+        let wait = rate_limit_wait(&headers(&[("retry-after", "-3")]));
+        assert_eq!(wait, Some(Duration::ZERO));
+        assert_eq!(parse_reset_duration("-5s"), None);
+    }
+
+    #[test]
+    fn rejects_garbage_and_non_finite_values() {
+        for value in ["", "soon", "5x", "1.2.3s", "s", "inf", "NaN", "-inf"] {
+            assert_eq!(parse_reset_duration(value), None, "{value:?}");
+        }
+        assert_eq!(parse_reset_duration("1e400"), None);
+        let huge = format!("{}h", "9".repeat(400));
+        assert_eq!(parse_reset_duration(&huge), None);
+        for value in ["inf", "1e400", "NaN", "tomorrow"] {
+            assert_eq!(
+                rate_limit_wait(&headers(&[("retry-after", value)])),
+                None,
+                "{value:?}"
+            );
+        }
+        assert_eq!(
+            rate_limit_wait(&headers(&[("retry-after", "1e300")])),
+            Some(MAX_RATE_LIMIT_WAIT)
+        );
+    }
+}
@@ -3,15 +3,28 @@ use serde::Serialize;
 use std::{str::FromStr, time::Duration};
 use tauri::{
     AppHandle, Emitter, Manager, Runtime,
-    menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, PredefinedMenuItem},
+    menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, PredefinedMenuItem, Submenu, SubmenuBuilder},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     WebviewUrl, WebviewWindowBuilder,
 };
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
+mod audio;
+mod chunking;
+mod clipboard;
+mod dictation;
+mod dsp;
+mod encode;
+mod glossary;
+mod hallucination;
+mod history;
+mod http_client;
 pub mod platform;
+mod refine;
+mod rules;
 mod settings;
 mod transcribe;
+mod vad;
 
 /// Wrapper for the platform FnKeyListener to implement required traits.
 struct FnKeyListenerWrapper(platform::FnKeyListener);
@@ -26,9 +39,24 @@ impl FnKeyListenerWrapper {
     }
 }
 
+/// What a recording is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordingMode {
+    /// Dictation, transcribed or translated.
+    Dictate(transcribe::TranscriptionTask),
+    /// A spoken instruction applied to the selected text.
+    Command,
+}
+
 struct AppState {
     session: std::sync::Mutex<Option<Box<dyn platform::RecordingHandle>>>,
+    /// What the current recording is for.
+    mode: std::sync::Mutex<RecordingMode>,
+    /// App that had focus when the current (or last) recording started.
+    target_app: std::sync::Mutex<Option<platform::AppIdentity>>,
     hotkey: std::sync::Mutex<Option<Shortcut>>,
+    translation_hotkey: std::sync::Mutex<Option<Shortcut>>,
+    command_hotkey: std::sync::Mutex<Option<Shortcut>>,
     fn_listener: std::sync::Mutex<Option<FnKeyListenerWrapper>>,
 }
 
@@ -36,7 +64,11 @@ impl Default for AppState {
     fn default() -> Self {
         Self {
             session: std::sync::Mutex::new(None),
+            mode: std::sync::Mutex::new(RecordingMode::Dictate(transcribe::TranscriptionTask::Transcribe)),
+            target_app: std::sync::Mutex::new(None),
             hotkey: std::sync::Mutex::new(None),
+            translation_hotkey: std::sync::Mutex::new(None),
+            command_hotkey: std::sync::Mutex::new(None),
             fn_listener: std::sync::Mutex::new(None),
         }
     }
@@ -60,16 +92,23 @@ fn emit_log<R: Runtime>(app: &AppHandle<R>, level: &str, message: impl Into<Stri
 
 // Default shortcut (platform-specific):
 // macOS: F13 — common "extra" function key on Mac keyboards.
-// Windows: Ctrl+Space — F13 doesn't exist on most PC keyboards.
+// Windows/Linux: Ctrl+Space — F13 doesn't exist on most PC keyboards.
 #[cfg(target_os = "macos")]
 const DEFAULT_HOTKEY: &str = "F13";
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux"))]
 const DEFAULT_HOTKEY: &str = "Control+Space";
 
 const DEFAULT_TRIGGER_MODE: &str = "hold";
 
 const DEFAULT_AUTO_TYPE_ENABLED: bool = true;
 const DEFAULT_TYPE_SPEED_MS: u64 = 0;
+const DEFAULT_TRIM_SILENCE_ENABLED: bool = true;
+const DEFAULT_HALLUCINATION_FILTER_ENABLED: bool = true;
+const DEFAULT_SPOKEN_COMMANDS_ENABLED: bool = false;
+const DEFAULT_REFINEMENT_DRIFT_GUARD: bool = true;
+const DEFAULT_REFINEMENT_STREAMING: bool = false;
+const DEFAULT_REFINEMENT_MODEL: &str = "qwen/qwen3-32b";
+const DEFAULT_REFINEMENT_TEMPERATURE: f32 = 0.1;
 
 fn resolve_auto_type_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
     settings::get_auto_type_enabled(app)
@@ -78,6 +117,76 @@ fn resolve_auto_type_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
         .unwrap_or(DEFAULT_AUTO_TYPE_ENABLED)
 }
 
+/// Task used by the main hotkey, the tray and the UI: "transcribe" unless
+/// translation mode is selected.
+fn resolve_transcription_task<R: Runtime>(app: &AppHandle<R>) -> transcribe::TranscriptionTask {
+    settings::get_transcription_mode(app)
+        .ok()
+        .flatten()
+        .and_then(|mode| transcribe::TranscriptionTask::parse(&mode))
+        .unwrap_or_default()
+}
+
+fn resolve_trim_silence_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_trim_silence_enabled(app)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_TRIM_SILENCE_ENABLED)
+}
+
+fn resolve_spoken_commands_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_spoken_commands_enabled(app)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_SPOKEN_COMMANDS_ENABLED)
+}
+
+fn resolve_refine_output_enabled<R: Runtime>(
+    app: &AppHandle<R>,
+    app_profile: Option<&settings::AppProfile>,
+) -> bool {
+    app_profile.and_then(|p| p.refine).unwrap_or_else(|| {
+        settings::get_refine_output_enabled(app)
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+    })
+}
+
+fn resolve_refinement_streaming<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_refinement_streaming(app)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_REFINEMENT_STREAMING)
+}
+
+fn resolve_refinement_drift_guard<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_refinement_drift_guard(app)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_REFINEMENT_DRIFT_GUARD)
+}
+
+fn resolve_hallucination_filter_enabled<R: Runtime>(app: &AppHandle<R>) -> bool {
+    settings::get_hallucination_filter_enabled(app)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_HALLUCINATION_FILTER_ENABLED)
+}
+
+/// Configured hallucination phrases, or the built-in list if unset.
+fn resolve_hallucination_blocklist<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    settings::get_hallucination_blocklist(app)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| {
+            hallucination::DEFAULT_BLOCKLIST
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        })
+}
+
 fn resolve_type_speed_ms<R: Runtime>(app: &AppHandle<R>) -> u64 {
     settings::get_type_speed_ms(app)
         .ok()
@@ -94,6 +203,24 @@ fn resolve_mic_gain<R: Runtime>(app: &AppHandle<R>) -> f32 {
         .unwrap_or(DEFAULT_MIC_GAIN)
 }
 
+/// The configured input device, or `None` for the default device.
+///
+/// Falls back to the default (with a log event) when the configured
+/// device is no longer connected.
+fn resolve_input_device<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    let name = settings::get_input_device(app).ok().flatten()?;
+    if audio::input_device_exists(&name) {
+        Some(name)
+    } else {
+        emit_log(
+            app,
+            "warn",
+            format!("Input device '{name}' not found; using the default input device"),
+        );
+        None
+    }
+}
+
 fn resolve_trigger_mode<R: Runtime>(app: &AppHandle<R>) -> String {
     settings::get_trigger_mode(app)
         .ok()
@@ -124,15 +251,15 @@ fn set_clipboard_text(text: &str) -> Result<(), String> {
 
 // Auto-typing implementation lives in src-tauri/src/auto_type.rs
 
+const ACCESSIBILITY_REQUIRED: &str = "Accessibility permission is required to type into other apps. Enable it in System Settings → Privacy & Security → Accessibility.";
+
 fn type_text_into_focused_app<R: Runtime>(app: &AppHandle<R>, text: &str) -> Result<(), String> {
     if text.trim().is_empty() {
         return Ok(());
     }
 
     if !is_accessibility_trusted() {
-        return Err(
-            "Accessibility permission is required to type into other apps. Enable it in System Settings → Privacy & Security → Accessibility.".into(),
-        );
+        return Err(ACCESSIBILITY_REQUIRED.into());
     }
 
     let delay_ms = resolve_type_speed_ms(app);
@@ -142,6 +269,231 @@ fn type_text_into_focused_app<R: Runtime>(app: &AppHandle<R>, text: &str) -> Res
     platform::current().type_text(text, delay)
 }
 
+/// Put `text` into the focused app with the given method.
+fn inject_text<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    method: settings::InjectionMethod,
+) -> Result<(), String> {
+    match method {
+        settings::InjectionMethod::Type => type_text_into_focused_app(app, text),
+        settings::InjectionMethod::Paste => paste_into_focused_app(text),
+    }
+}
+
+/// Paste text through the clipboard, restoring its previous contents.
+fn paste_into_focused_app(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+
+    if !is_accessibility_trusted() {
+        return Err(ACCESSIBILITY_REQUIRED.into());
+    }
+
+    clipboard::paste_text(text, || platform::current().press_paste_shortcut())
+}
+
+/// The app profile's injection method, else the global one (default: type).
+fn resolve_injection_method<R: Runtime>(
+    app: &AppHandle<R>,
+    app_profile: Option<&settings::AppProfile>,
+) -> settings::InjectionMethod {
+    app_profile
+        .and_then(|p| p.injection)
+        .or_else(|| settings::get_injection_method(app).ok().flatten())
+        .unwrap_or_default()
+}
+
+/// Overrides for the app that had focus when recording started, if it has a
+/// profile.
+fn resolve_app_profile<R: Runtime>(
+    app: &AppHandle<R>,
+    target_app: Option<&platform::AppIdentity>,
+) -> Option<settings::AppProfile> {
+    let target = target_app?;
+    let profile = settings::get_app_profiles(app)
+        .ok()?
+        .remove(&settings::app_profile_key(&target.id))?;
+    let label = profile
+        .name
+        .as_deref()
+        .or(target.name.as_deref())
+        .unwrap_or(&target.id);
+    emit_log(app, "info", format!("Using app profile for {label}"));
+    Some(profile)
+}
+
+const DEFAULT_TRANSCRIPTION_PROVIDER: &str = "groq";
+
+/// API key resolution: settings.json > env var
+fn resolve_groq_api_key<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    settings::get_groq_api_key(app)
+        .ok()
+        .flatten()
+        .or_else(|| std::env::var("GROQ_API_KEY").ok())
+}
+
+fn resolve_transcription_provider_name<R: Runtime>(app: &AppHandle<R>) -> String {
+    settings::get_transcription_provider(app)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| DEFAULT_TRANSCRIPTION_PROVIDER.to_string())
+}
+
+/// Endpoint config for the Groq (or gateway) Whisper and chat calls.
+fn resolve_api_config<R: Runtime>(app: &AppHandle<R>, api_key: Option<String>) -> transcribe::ApiConfig {
+    let s = settings::load(app).unwrap_or_default();
+    transcribe::ApiConfig {
+        base_url: s
+            .api_base_url
+            .unwrap_or_else(|| transcribe::GROQ_API_BASE_URL.to_string()),
+        api_key,
+        extra_headers: s.extra_headers.unwrap_or_default(),
+    }
+}
+
+fn resolve_transcription_model<R: Runtime>(app: &AppHandle<R>) -> String {
+    settings::get_transcription_model(app)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| transcribe::GROQ_TRANSCRIPTION_MODEL.to_string())
+}
+
+/// Configured upload encoding. Falls back to WAV (with a log event) if the
+/// setting names an encoding this build doesn't support.
+fn resolve_upload_encoding<R: Runtime>(app: &AppHandle<R>) -> encode::UploadEncoding {
+    let Some(name) = settings::get_upload_encoding(app).ok().flatten() else {
+        return encode::UploadEncoding::Wav;
+    };
+    match encode::UploadEncoding::parse(&name) {
+        Some(e) if e.is_available() => e,
+        _ => {
+            emit_log(
+                app,
+                "warn",
+                format!("Upload encoding '{name}' is not available in this build; using wav"),
+            );
+            encode::UploadEncoding::Wav
+        }
+    }
+}
+
+fn resolve_upload_options<R: Runtime>(app: &AppHandle<R>) -> transcribe::UploadOptions {
+    let max_segment = settings::get_max_segment_secs(app)
+        .ok()
+        .flatten()
+        // The setter validates the range; a hand-edited file may not.
+        .map(|secs| {
+            let range = settings::MAX_SEGMENT_SECS_RANGE;
+            Duration::from_secs(secs.clamp(*range.start(), *range.end()) as u64)
+        })
+        .unwrap_or(transcribe::DEFAULT_MAX_SEGMENT);
+    transcribe::UploadOptions {
+        encoding: resolve_upload_encoding(app),
+        max_segment,
+    }
+}
+
+/// Whisper parameters for `task`. Language, prompt and temperature come from
+/// the active transcription profile where set, otherwise the global settings;
+/// glossary terms are appended to the prompt. An app profile's language wins
+/// over both.
+fn resolve_whisper_options<R: Runtime>(
+    app: &AppHandle<R>,
+    task: transcribe::TranscriptionTask,
+    app_profile: Option<&settings::AppProfile>,
+) -> transcribe::WhisperOptions {
+    let s = settings::load(app).unwrap_or_default();
+    let profile = match s.active_transcription_profile.as_deref() {
+        Some(name) => {
+            let profile = s
+                .transcription_profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(name))
+                .cloned();
+            if profile.is_none() {
+                emit_log(
+                    app,
+                    "warn",
+                    format!("Transcription profile '{name}' no longer exists; using global settings"),
+                );
+            }
+            profile.unwrap_or_default()
+        }
+        None => settings::TranscriptionProfile::default(),
+    };
+    let language = match app_profile.and_then(|p| p.language.as_deref()) {
+        Some("auto") => None,
+        Some(code) => Some(code.to_string()),
+        None => profile.language.or(s.transcription_language),
+    };
+    transcribe::WhisperOptions {
+        task,
+        language,
+        prompt: glossary::build_prompt(
+            profile.prompt.or(s.transcription_prompt).as_deref(),
+            s.glossary.as_deref().unwrap_or_default(),
+        ),
+        temperature: profile.temperature.or(s.transcription_temperature),
+    }
+}
+
+/// Build the transcription provider selected in settings.
+///
+/// `model_override` replaces the configured model (ignored by the local
+/// command provider). Translation needs one of the API providers.
+fn resolve_transcription_provider<R: Runtime>(
+    app: &AppHandle<R>,
+    model_override: Option<String>,
+    whisper: transcribe::WhisperOptions,
+) -> Result<Box<dyn transcribe::TranscriptionProvider>, String> {
+    let s = settings::load(app)?;
+
+    match s
+        .transcription_provider
+        .as_deref()
+        .unwrap_or(DEFAULT_TRANSCRIPTION_PROVIDER)
+    {
+        "openai_compatible" => Ok(Box::new(transcribe::OpenAiCompatibleProvider {
+            api: transcribe::ApiConfig {
+                base_url: s.openai_compatible_base_url.ok_or_else(|| {
+                    "Missing base URL for the OpenAI-compatible provider. Set it in the app settings."
+                        .to_string()
+                })?,
+                api_key: s.openai_compatible_api_key,
+                extra_headers: s.extra_headers.unwrap_or_default(),
+            },
+            model: model_override.or(s.openai_compatible_model).unwrap_or_else(|| {
+                transcribe::GROQ_TRANSCRIPTION_MODEL.to_string()
+            }),
+            upload: resolve_upload_options(app),
+            whisper,
+        })),
+        "local_command" if whisper.task == transcribe::TranscriptionTask::Translate => Err(
+            "Translation needs the Groq or OpenAI-compatible provider; the local command provider can only transcribe."
+                .to_string(),
+        ),
+        "local_command" => Ok(Box::new(transcribe::LocalCommandProvider {
+            command: s.local_command.ok_or_else(|| {
+                "Missing executable for the local command provider. Set it in the app settings."
+                    .to_string()
+            })?,
+            args: s.local_command_args.unwrap_or_default(),
+        })),
+        _ => {
+            let api_key = resolve_groq_api_key(app)
+                .ok_or_else(|| "Missing Groq API key. Set it in the app settings.".to_string())?;
+            Ok(Box::new(transcribe::GroqProvider {
+                api: resolve_api_config(app, Some(api_key)),
+                model: model_override.unwrap_or_else(|| resolve_transcription_model(app)),
+                upload: resolve_upload_options(app),
+                whisper,
+            }))
+        }
+    }
+}
+
 fn resolve_hotkey_string<R: Runtime>(app: &AppHandle<R>) -> String {
     settings::get_global_hotkey(app)
         .ok()
@@ -153,12 +505,67 @@ fn register_hotkey<R: Runtime>(
     app: &AppHandle<R>,
     state: &AppState,
     hotkey_str: &str,
+) -> Result<(), String> {
+    register_shortcut(app, &state.hotkey, hotkey_str, None)
+}
+
+/// Register the hotkey that always records in translation mode.
+fn register_translation_hotkey<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    hotkey_str: &str,
+) -> Result<(), String> {
+    register_shortcut(
+        app,
+        &state.translation_hotkey,
+        hotkey_str,
+        Some(RecordingMode::Dictate(transcribe::TranscriptionTask::Translate)),
+    )
+}
+
+fn unregister_translation_hotkey<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> Result<(), String> {
+    unregister_shortcut(app, &state.translation_hotkey)
+}
+
+/// Register the hotkey that records in command mode.
+fn register_command_hotkey<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    hotkey_str: &str,
+) -> Result<(), String> {
+    register_shortcut(app, &state.command_hotkey, hotkey_str, Some(RecordingMode::Command))
+}
+
+fn unregister_command_hotkey<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> Result<(), String> {
+    unregister_shortcut(app, &state.command_hotkey)
+}
+
+fn unregister_shortcut<R: Runtime>(
+    app: &AppHandle<R>,
+    slot: &std::sync::Mutex<Option<Shortcut>>,
+) -> Result<(), String> {
+    let mut guard = slot.lock().map_err(|e| e.to_string())?;
+    if let Some(prev) = guard.take() {
+        let _ = app.global_shortcut().unregister(prev);
+    }
+    Ok(())
+}
+
+/// Register `hotkey_str` in `slot`, replacing the shortcut held there.
+///
+/// Recordings started with it use `recording`, or dictation in the
+/// configured transcription mode when `None`.
+fn register_shortcut<R: Runtime>(
+    app: &AppHandle<R>,
+    slot: &std::sync::Mutex<Option<Shortcut>>,
+    hotkey_str: &str,
+    recording: Option<RecordingMode>,
 ) -> Result<(), String> {
     let shortcut = Shortcut::from_str(hotkey_str)
         .map_err(|e| format!("Invalid hotkey '{hotkey_str}': {e}"))?;
 
     // Unregister previous hotkey (best-effort).
-    let mut guard = state.hotkey.lock().map_err(|e| e.to_string())?;
+    let mut guard = slot.lock().map_err(|e| e.to_string())?;
     if let Some(prev) = guard.take() {
         let _ = app.global_shortcut().unregister(prev);
     }
@@ -170,6 +577,8 @@ fn register_hotkey<R: Runtime>(
 
             // Capture trigger mode at event time (settings can change at runtime).
             let mode = resolve_trigger_mode(&app);
+            let recording = recording
+                .unwrap_or_else(|| RecordingMode::Dictate(resolve_transcription_task(&app)));
 
             match mode.as_str() {
                 "toggle" => {
@@ -190,31 +599,14 @@ fn register_hotkey<R: Runtime>(
                             .unwrap_or(false);
 
                         if !is_recording {
-                            if let Err(e) = do_start_recording(&app, state.inner()) {
+                            if let Err(e) = do_start_recording_as(&app, state.inner(), recording) {
                                 emit_log(&app, "error", format!("Failed to start recording: {e}"));
                             }
                             return;
                         }
 
                         match do_stop_and_transcribe(&app, state.inner()).await {
-                            Ok(text) => {
-                                let _ = app.emit("transcription", text.clone());
-
-                                if resolve_auto_type_enabled(&app) {
-                                    match type_text_into_focused_app(&app, &text) {
-                                        Ok(()) => emit_log(
-                                            &app,
-                                            "info",
-                                            "Auto-typed transcription into focused app",
-                                        ),
-                                        Err(e) => emit_log(
-                                            &app,
-                                            "error",
-                                            format!("Auto-type failed: {e}"),
-                                        ),
-                                    }
-                                }
-                            }
+                            Ok(dictation) => deliver_transcription(&app, &dictation),
                             Err(e) => {
                                 emit_log(&app, "error", format!("Failed to stop/transcribe: {e}"))
                             }
@@ -226,7 +618,7 @@ fn register_hotkey<R: Runtime>(
                     match event.state {
                         ShortcutState::Pressed => {
                             let state = app.state::<AppState>();
-                            if let Err(e) = do_start_recording(&app, state.inner()) {
+                            if let Err(e) = do_start_recording_as(&app, state.inner(), recording) {
                                 // Ignore "Already recording" for a held key repeat.
                                 if e != "Already recording" {
                                     emit_log(&app, "error", format!("Failed to start recording: {e}"));
@@ -248,24 +640,7 @@ fn register_hotkey<R: Runtime>(
                                 }
 
                                 match do_stop_and_transcribe(&app, state.inner()).await {
-                                    Ok(text) => {
-                                        let _ = app.emit("transcription", text.clone());
-
-                                        if resolve_auto_type_enabled(&app) {
-                                            match type_text_into_focused_app(&app, &text) {
-                                                Ok(()) => emit_log(
-                                                    &app,
-                                                    "info",
-                                                    "Auto-typed transcription into focused app",
-                                                ),
-                                                Err(e) => emit_log(
-                                                    &app,
-                                                    "error",
-                                                    format!("Auto-type failed: {e}"),
-                                                ),
-                                            }
-                                        }
-                                    }
+                                    Ok(dictation) => deliver_transcription(&app, &dictation),
                                     Err(e) => emit_log(
                                         &app,
                                         "error",
@@ -290,16 +665,41 @@ fn register_hotkey<R: Runtime>(
     Ok(())
 }
 
+/// Start recording in the configured transcription mode.
 fn do_start_recording<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> Result<(), String> {
+    let task = resolve_transcription_task(app);
+    do_start_recording_as(app, state, RecordingMode::Dictate(task))
+}
+
+fn do_start_recording_as<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    mode: RecordingMode,
+) -> Result<(), String> {
     let mut guard = state.session.lock().map_err(|e| e.to_string())?;
     if guard.is_some() {
         return Err("Already recording".into());
     }
 
-    emit_log(app, "info", "Starting recording...");
-    let gain = resolve_mic_gain(app);
-    let session = platform::current().start_audio_capture(gain)?;
+    emit_log(
+        app,
+        "info",
+        match mode {
+            RecordingMode::Dictate(transcribe::TranscriptionTask::Transcribe) => "Starting recording...",
+            RecordingMode::Dictate(transcribe::TranscriptionTask::Translate) => {
+                "Starting recording (translate to English)..."
+            }
+            RecordingMode::Command => "Starting recording (command mode)...",
+        },
+    );
+    let options = audio::CaptureOptions {
+        device_name: resolve_input_device(app),
+        ..audio::CaptureOptions::new(resolve_mic_gain(app))
+    };
+    let session = platform::current().start_audio_capture(options)?;
     *guard = Some(session);
+    *state.mode.lock().map_err(|e| e.to_string())? = mode;
+    *state.target_app.lock().map_err(|e| e.to_string())? = platform::current().focused_app();
     let _ = app.emit("recording_state", "recording");
     // Show floating overlay
     if let Some(w) = app.get_webview_window("overlay") {
@@ -309,10 +709,22 @@ fn do_start_recording<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> Resul
     Ok(())
 }
 
+/// A finished dictation, ready to hand to the UI.
+#[derive(Default)]
+struct Dictation {
+    /// Final text; empty if no speech was detected.
+    text: String,
+    /// Already put into the focused app: typed while refinement streamed, or
+    /// replacing the selection in command mode.
+    typed: bool,
+    /// How to put the text into the focused app.
+    injection: settings::InjectionMethod,
+}
+
 async fn do_stop_and_transcribe<R: Runtime>(
     app: &AppHandle<R>,
     state: &AppState,
-) -> Result<String, String> {
+) -> Result<Dictation, String> {
     let session = {
         let mut guard = state.session.lock().map_err(|e| e.to_string())?;
         guard.take()
@@ -321,9 +733,16 @@ async fn do_stop_and_transcribe<R: Runtime>(
     let Some(session) = session else {
         return Err("Not recording".into());
     };
+    let mode = *state.mode.lock().map_err(|e| e.to_string())?;
+    let target_app = state.target_app.lock().map_err(|e| e.to_string())?.clone();
 
     let _ = app.emit("recording_state", "processing");
-    let result = do_transcription_pipeline(app, session).await;
+    let result = match mode {
+        RecordingMode::Dictate(task) => {
+            do_transcription_pipeline(app, session, task, target_app.as_ref()).await
+        }
+        RecordingMode::Command => do_command_pipeline(app, session, target_app.as_ref()).await,
+    };
     let _ = app.emit("recording_state", "idle");
     // Hide floating overlay
     if let Some(w) = app.get_webview_window("overlay") {
@@ -332,128 +751,872 @@ async fn do_stop_and_transcribe<R: Runtime>(
     result
 }
 
-/// Inner pipeline: stop recording → transcribe → optionally refine.
+/// Inner pipeline: stop recording → trim silence → transcribe → optionally refine → save to history
+/// → prepare for typing. History keeps the text from before spoken commands and rules ran.
+/// With streaming refinement, the text is typed here as it arrives (see `Dictation::typed`).
+/// Returns empty text (without calling the API) if no speech was detected.
 /// Separated so `do_stop_and_transcribe` can always emit "idle" on completion.
 async fn do_transcription_pipeline<R: Runtime>(
     app: &AppHandle<R>,
     session: Box<dyn platform::RecordingHandle>,
-) -> Result<String, String> {
+    task: transcribe::TranscriptionTask,
+    target_app: Option<&platform::AppIdentity>,
+) -> Result<Dictation, String> {
     emit_log(app, "info", "Stopping recording...");
     let wav_path = session.stop_and_save_wav()?;
-    emit_log(app, "info", format!("Saved WAV: {}", wav_path.display()));
 
-    // API key resolution: settings.json > env var
-    let api_key = settings::get_groq_api_key(app)
-        .ok()
-        .flatten()
-        .or_else(|| std::env::var("GROQ_API_KEY").ok())
-        .ok_or_else(|| "Missing Groq API key. Set it in the app settings.".to_string())?;
+    if !trim_recording_silence(app, &wav_path) {
+        return Ok(Dictation::default());
+    }
 
-    emit_log(app, "info", "Transcribing with Groq...");
-    let text = transcribe::transcribe_groq(wav_path, api_key.clone())
-        .await
-        .map_err(|e| e.to_string())?;
-    emit_log(app, "info", "Transcription completed");
+    // Keep the recording with history so it survives a failed transcription.
+    let wav_path = match history::store_recording(app, &wav_path) {
+        Ok(stored) => stored,
+        Err(e) => {
+            emit_log(app, "error", format!("Failed to keep recording: {e}"));
+            wav_path
+        }
+    };
+    emit_log(app, "info", format!("Saved WAV: {}", wav_path.display()));
 
-    // Check if refinement is enabled
-    let refine_enabled = settings::get_refine_output_enabled(app)
-        .ok()
-        .flatten()
-        .unwrap_or(false);
+    let app_profile = resolve_app_profile(app, target_app);
+    let app_profile = app_profile.as_ref();
+    let whisper = resolve_whisper_options(app, task, app_profile);
+    let pinned_language = whisper.language.clone();
+    let provider = resolve_transcription_provider(app, None, whisper)?;
+    let translated = task == transcribe::TranscriptionTask::Translate;
+
+    let action = if translated { "Translating" } else { "Transcribing" };
+    emit_log(app, "info", format!("{action} with {}...", provider.name()));
+    let transcript = match provider.transcribe(wav_path.clone()).await {
+        Ok(transcript) => transcript,
+        Err(e) => {
+            let error = e.to_string();
+            save_history_entry(
+                app,
+                history::NewEntry {
+                    raw_text: String::new(),
+                    refined_text: None,
+                    model: provider.model().to_string(),
+                    refinement_model: None,
+                    duration_ms: history::wav_duration_ms(&wav_path),
+                    wav_path: Some(wav_path),
+                    error: Some(error.clone()),
+                    language: None,
+                    translated,
+                },
+            );
+            return Err(error);
+        }
+    };
+    emit_log(app, "info", "Transcription completed");
+    if let Some(language) = &transcript.language {
+        let label = if translated { "Source language" } else { "Detected language" };
+        emit_log(app, "info", format!("{label}: {language}"));
+    }
 
-    if refine_enabled {
-        emit_log(app, "info", "Refining transcription with Qwen model...");
-        let custom_prompt = settings::get_refinement_prompt(app)
-            .ok()
-            .flatten()
-            .unwrap_or_default();
-        let model = settings::get_refinement_model(app)
-            .ok()
-            .flatten()
-            .unwrap_or_else(|| "qwen/qwen3-32b".to_string());
+    if let Some(reason) = detect_hallucination(app, &transcript, &wav_path) {
+        emit_log(
+            app,
+            "warn",
+            format!("Dropped suspected hallucination \"{}\": {reason}", transcript.text.trim()),
+        );
+        save_history_entry(
+            app,
+            history::NewEntry {
+                raw_text: transcript.text,
+                refined_text: None,
+                model: provider.model().to_string(),
+                refinement_model: None,
+                duration_ms: history::wav_duration_ms(&wav_path),
+                wav_path: Some(wav_path),
+                error: Some(format!("Suspected hallucination: {reason}")),
+                language: transcript.language,
+                translated,
+            },
+        );
+        return Ok(Dictation::default());
+    }
+    let text = apply_glossary(app, &transcript.text);
+    let language = transcript.language;
+    // Spoken commands are looked up in the output language's table.
+    let output_language = if translated {
+        Some("en".to_string())
+    } else {
+        language
+            .clone()
+            .or(pinned_language)
+    };
 
-        match transcribe::refine_transcript(api_key, text.clone(), custom_prompt, model).await {
-            Ok(refined_text) => {
-                emit_log(app, "info", "Refinement completed successfully");
-                Ok(refined_text)
-            }
-            Err(e) => {
-                emit_log(app, "error", format!("Refinement failed: {}. Using original transcript.", e));
-                Ok(text)
-            }
-        }
+    let (refined, typed) = if resolve_refinement_streaming(app) && resolve_auto_type_enabled(app) {
+        let streamed = do_streaming_refinement(app, &text, output_language.as_deref(), app_profile).await;
+        (streamed.refinement, streamed.typed)
     } else {
-        Ok(text)
-    }
+        (do_refinement(app, &text, app_profile).await, None)
+    };
+    let final_text = refined
+        .as_ref()
+        .map(|r| r.text.clone())
+        .unwrap_or_else(|| text.clone());
+
+    save_history_entry(
+        app,
+        history::NewEntry {
+            raw_text: text,
+            duration_ms: history::wav_duration_ms(&wav_path),
+            model: provider.model().to_string(),
+            refinement_model: refined.as_ref().map(|r| r.model.clone()),
+            refined_text: refined.map(|r| r.text),
+            wav_path: Some(wav_path),
+            error: None,
+            language,
+            translated,
+        },
+    );
+
+    let injection = resolve_injection_method(app, app_profile);
+    Ok(match typed {
+        Some(text) => Dictation { text, typed: true, injection },
+        None => Dictation {
+            text: prepare_for_typing(app, &final_text, output_language.as_deref(), app_profile),
+            typed: false,
+            injection,
+        },
+    })
 }
 
-#[tauri::command]
-fn start_recording(state: tauri::State<'_, AppState>, app: AppHandle) -> Result<(), String> {
-    do_start_recording(&app, state.inner())
+/// Trim leading/trailing silence from the recording, if enabled. Returns
+/// `false` (and deletes the recording) when it contains no speech at all.
+fn trim_recording_silence<R: Runtime>(app: &AppHandle<R>, wav_path: &std::path::Path) -> bool {
+    if !resolve_trim_silence_enabled(app) {
+        return true;
+    }
+    match vad::trim_silence(wav_path) {
+        Ok(report) if !report.has_speech => {
+            emit_log(app, "info", "No speech detected; skipping transcription");
+            let _ = app.emit("no_speech_detected", report.original_ms);
+            let _ = std::fs::remove_file(wav_path);
+            return false;
+        }
+        Ok(report) if report.removed_ms > 0 => emit_log(
+            app,
+            "info",
+            format!(
+                "Trimmed {:.1}s of silence ({:.1}s of audio left)",
+                report.removed_ms as f64 / 1000.0,
+                report.original_ms.saturating_sub(report.removed_ms) as f64 / 1000.0
+            ),
+        ),
+        Ok(_) => {}
+        Err(e) => emit_log(app, "warn", format!("Silence trimming failed: {e}")),
+    }
+    true
 }
 
-#[tauri::command]
-async fn stop_and_transcribe(
-    state: tauri::State<'_, AppState>,
-    app: AppHandle,
-) -> Result<String, String> {
-    let text = do_stop_and_transcribe(&app, state.inner()).await?;
-    let _ = app.emit("transcription", text.clone());
+// ─────────────────────────────────────────────────────────────────────────────
+// Command mode
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Command mode pipeline: stop recording → copy the selection → transcribe
+/// the spoken instruction → apply it to the selection with the chat model →
+/// replace the selection with the result.
+///
+/// The instruction isn't dictation, so it skips history, spoken commands and
+/// replacement rules, and the result replaces the selection even with
+/// auto-type off. Returns empty text if nothing was selected or said.
+async fn do_command_pipeline<R: Runtime>(
+    app: &AppHandle<R>,
+    session: Box<dyn platform::RecordingHandle>,
+    target_app: Option<&platform::AppIdentity>,
+) -> Result<Dictation, String> {
+    emit_log(app, "info", "Stopping recording...");
+    let wav_path = session.stop_and_save_wav()?;
 
-    if resolve_auto_type_enabled(&app) {
-        match type_text_into_focused_app(&app, &text) {
-            Ok(()) => emit_log(&app, "info", "Auto-typed transcription into focused app"),
-            Err(e) => emit_log(&app, "error", format!("Auto-type failed: {e}")),
+    // Copy while the target app still has focus and the selection is fresh.
+    let selection = clipboard::copy_selection(|| platform::current().press_copy_shortcut());
+    let selection = match selection {
+        Ok(Some(selection)) if !selection.trim().is_empty() => selection,
+        Ok(_) => {
+            let _ = std::fs::remove_file(&wav_path);
+            emit_log(app, "warn", "Command mode needs selected text; nothing was selected");
+            return Ok(Dictation::default());
         }
+        Err(e) => {
+            let _ = std::fs::remove_file(&wav_path);
+            return Err(format!("Failed to copy the selection: {e}"));
+        }
+    };
+
+    if !trim_recording_silence(app, &wav_path) {
+        return Ok(Dictation::default());
     }
 
-    Ok(text)
-}
+    let app_profile = resolve_app_profile(app, target_app);
+    let app_profile = app_profile.as_ref();
+    let whisper = resolve_whisper_options(app, transcribe::TranscriptionTask::Transcribe, app_profile);
+    let provider = resolve_transcription_provider(app, None, whisper)?;
 
-#[tauri::command]
-fn recording_status(state: tauri::State<'_, AppState>) -> Result<bool, String> {
-    let guard = state.inner().session.lock().map_err(|e| e.to_string())?;
-    Ok(guard.is_some())
-}
+    emit_log(app, "info", format!("Transcribing command with {}...", provider.name()));
+    let transcript = provider.transcribe(wav_path.clone()).await;
+    let hallucination = transcript
+        .as_ref()
+        .ok()
+        .and_then(|transcript| detect_hallucination(app, transcript, &wav_path));
+    let _ = std::fs::remove_file(&wav_path);
+    let transcript = transcript.map_err(|e| e.to_string())?;
+    if let Some(reason) = hallucination {
+        emit_log(
+            app,
+            "warn",
+            format!("Dropped suspected hallucination \"{}\": {reason}", transcript.text.trim()),
+        );
+        return Ok(Dictation::default());
+    }
+    let instruction = apply_glossary(app, transcript.text.trim());
+    // Without an instruction the model would just rewrite or echo the
+    // selection, and that would replace it.
+    if instruction.trim().is_empty() {
+        emit_log(app, "info", "No command heard; leaving the selection alone");
+        return Ok(Dictation::default());
+    }
+    emit_log(app, "info", format!("Command: {instruction}"));
 
-#[tauri::command]
-fn set_groq_api_key(app: AppHandle, api_key: String) -> Result<(), String> {
-    settings::set_groq_api_key(&app, api_key)
-}
+    // Like refinement, this always goes through Groq.
+    let api_key = resolve_groq_api_key(app)
+        .ok_or_else(|| "Missing Groq API key. Set it in the app settings.".to_string())?;
+    let api = resolve_api_config(app, Some(api_key));
+    let options = resolve_refinement_options(app, app_profile);
+    emit_log(app, "info", format!("Editing selection with {}...", options.model));
+    let edited = transcribe::edit_selection(&api, &selection, &instruction, &options)
+        .await
+        .map_err(|e| format!("Command failed: {e}"))?;
+    let edited = refine::keep_surrounding_whitespace(&selection, &edited);
 
-#[tauri::command]
-fn get_groq_api_key(app: AppHandle) -> Result<Option<String>, String> {
-    settings::get_groq_api_key(&app)
+    let injection = resolve_injection_method(app, app_profile);
+    inject_text(app, &edited, injection)?;
+    emit_log(app, "info", "Replaced selection with the command result");
+    Ok(Dictation { text: edited, typed: true, injection })
 }
 
-#[tauri::command]
-fn get_hotkey(app: AppHandle) -> Result<String, String> {
-    Ok(resolve_hotkey_string(&app))
+/// Why the transcript looks like a Whisper hallucination, if the filter is
+/// enabled and it does.
+fn detect_hallucination<R: Runtime>(
+    app: &AppHandle<R>,
+    transcript: &transcribe::Transcript,
+    wav_path: &std::path::Path,
+) -> Option<String> {
+    if !resolve_hallucination_filter_enabled(app) {
+        return None;
+    }
+    let audio = match hallucination::AudioStats::from_wav(wav_path) {
+        Ok(stats) => Some(stats),
+        Err(e) => {
+            emit_log(app, "warn", format!("Could not analyze recording loudness: {e}"));
+            None
+        }
+    };
+    hallucination::check(transcript, audio.as_ref(), &resolve_hallucination_blocklist(app))
 }
 
-#[tauri::command]
-fn set_hotkey(
-    app: AppHandle,
-    state: tauri::State<'_, AppState>,
-    hotkey: String,
-) -> Result<(), String> {
-    let hotkey = hotkey.trim().to_string();
-    if hotkey.is_empty() {
-        return Err("Hotkey cannot be empty".into());
+/// Replace misspelled glossary terms with their canonical spelling.
+fn apply_glossary<R: Runtime>(app: &AppHandle<R>, text: &str) -> String {
+    let entries = settings::get_glossary(app).unwrap_or_default();
+    if entries.is_empty() {
+        return text.to_string();
     }
-
-    // Validate + register first, so we don't persist a broken value.
-    register_hotkey(&app, state.inner(), &hotkey)?;
-    settings::set_global_hotkey(&app, hotkey)
+    let replacer = match glossary::Replacer::new(&entries) {
+        Ok(replacer) => replacer,
+        Err(e) => {
+            emit_log(app, "warn", format!("Skipped glossary corrections: {e:#}"));
+            return text.to_string();
+        }
+    };
+    let (corrected, changed) = replacer.apply(text);
+    if changed > 0 {
+        emit_log(app, "info", format!("Glossary corrected {changed} term(s)"));
+    }
+    corrected
 }
 
-#[tauri::command]
+/// Turn the final transcript into the text to type: interpret spoken
+/// commands (if enabled), then run the replacement rules.
+fn prepare_for_typing<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    language: Option<&str>,
+    app_profile: Option<&settings::AppProfile>,
+) -> String {
+    let text = if resolve_spoken_commands_enabled(app) {
+        dictation::interpret(text, language)
+    } else {
+        text.to_string()
+    };
+    apply_replacement_rules(app, &text, app_profile)
+}
+
+/// Run the enabled replacement/snippet rules on text about to be typed: the
+/// global rules, then the app profile's. Rules with an invalid pattern are
+/// skipped with a log event.
+fn apply_replacement_rules<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    app_profile: Option<&settings::AppProfile>,
+) -> String {
+    let mut rules = settings::get_replacement_rules(app).unwrap_or_default();
+    if let Some(profile) = app_profile {
+        rules.extend(profile.replacement_rules.iter().cloned());
+    }
+    if rules.is_empty() {
+        return text.to_string();
+    }
+    let outcome = rules::apply(&rules, text);
+    for (id, error) in &outcome.errors {
+        emit_log(app, "warn", format!("Skipped replacement rule {id}: {error}"));
+    }
+    if !outcome.applied.is_empty() {
+        emit_log(
+            app,
+            "info",
+            format!("Applied {} replacement rule(s)", outcome.applied.len()),
+        );
+    }
+    outcome.text
+}
+
+/// Hand a finished transcription to the UI and, if enabled and not done
+/// while streaming, type it into the focused app. Empty results (no speech
+/// detected) are dropped.
+fn deliver_transcription<R: Runtime>(app: &AppHandle<R>, dictation: &Dictation) {
+    let text = dictation.text.as_str();
+    if text.trim().is_empty() {
+        return;
+    }
+    let _ = app.emit("transcription", text.to_string());
+
+    if resolve_auto_type_enabled(app) && !dictation.typed {
+        match inject_text(app, text, dictation.injection) {
+            Ok(()) => emit_log(app, "info", "Auto-typed transcription into focused app"),
+            Err(e) => emit_log(app, "error", format!("Auto-type failed: {e}")),
+        }
+    }
+}
+
+fn save_history_entry<R: Runtime>(app: &AppHandle<R>, entry: history::NewEntry) {
+    match history::add(app, entry) {
+        Ok(entry) => {
+            let _ = app.emit("history_updated", entry.id);
+        }
+        Err(e) => emit_log(app, "error", format!("Failed to save history entry: {e}")),
+    }
+}
+
+struct Refinement {
+    text: String,
+    model: String,
+}
+
+/// Refine `text` if refinement is enabled in settings.
+///
+/// Returns `None` when refinement is disabled or fails (the failure is logged),
+/// so callers fall back to the original transcript.
+async fn do_refinement<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    app_profile: Option<&settings::AppProfile>,
+) -> Option<Refinement> {
+    if !resolve_refine_output_enabled(app, app_profile) {
+        return None;
+    }
+
+    let options = resolve_refinement_options(app, app_profile);
+    emit_log(app, "info", format!("Refining transcription with {}...", options.model));
+
+    match refine_with(app, text, &options).await {
+        Ok(refined_text) => {
+            if options.drift_guard {
+                if let Some(reason) = refine::check_drift(text, &refined_text) {
+                    emit_log(
+                        app,
+                        "warn",
+                        format!("Refinement drifted from the transcript ({reason}). Using original transcript."),
+                    );
+                    return None;
+                }
+            }
+            emit_log(app, "info", "Refinement completed successfully");
+            Some(Refinement {
+                text: refined_text,
+                model: options.model,
+            })
+        }
+        Err(e) => {
+            emit_log(app, "error", format!("Refinement failed: {}. Using original transcript.", e));
+            None
+        }
+    }
+}
+
+/// Outcome of [`do_streaming_refinement`].
+#[derive(Default)]
+struct StreamedRefinement {
+    /// The complete refinement; `None` if disabled, failed or rejected.
+    refinement: Option<Refinement>,
+    /// What was typed while streaming, if anything. The rest of the pipeline
+    /// must not type the text again.
+    typed: Option<String>,
+}
+
+/// Refine `text` with a streamed response, typing it sentence by sentence as
+/// it arrives instead of after the whole response.
+///
+/// With the drift guard on, nothing is typed until the response has enough
+/// words to check (see [`STREAM_HOLD_WORDS`]), and typing stops if it drifts
+/// later. If the stream fails or stops before anything was typed, this
+/// behaves like a failed [`do_refinement`]. If it fails part way, the
+/// untyped rest of the raw transcript is typed instead.
+async fn do_streaming_refinement<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    language: Option<&str>,
+    app_profile: Option<&settings::AppProfile>,
+) -> StreamedRefinement {
+    if !resolve_refine_output_enabled(app, app_profile) {
+        return StreamedRefinement::default();
+    }
+    let api_key = match resolve_groq_api_key(app) {
+        Some(key) => key,
+        None => {
+            emit_log(app, "error", "Refinement failed: Missing Groq API key. Using original transcript.");
+            return StreamedRefinement::default();
+        }
+    };
+
+    let options = resolve_refinement_options(app, app_profile);
+    emit_log(app, "info", format!("Refining transcription with {} (streaming)...", options.model));
+    let api = resolve_api_config(app, Some(api_key));
+    let mut typer = StreamTyper {
+        app,
+        raw: text,
+        language,
+        app_profile,
+        injection: resolve_injection_method(app, app_profile),
+        guard: options.drift_guard,
+        pending: String::new(),
+        refined_typed: String::new(),
+        typed: String::new(),
+        stop_reason: None,
+    };
+
+    let result =
+        transcribe::refine_transcript_stream(&api, text.to_string(), &options, |delta| typer.push(delta)).await;
+    let model = options.model;
+
+    match result {
+        Ok(refined_text) if typer.typed.is_empty() => {
+            // Short enough to arrive before anything was typed: check it
+            // whole, and leave typing to the pipeline.
+            if typer.guard {
+                if let Some(reason) = refine::check_drift(text, &refined_text) {
+                    emit_log(
+                        app,
+                        "warn",
+                        format!("Refinement drifted from the transcript ({reason}). Using original transcript."),
+                    );
+                    return StreamedRefinement::default();
+                }
+            }
+            emit_log(app, "info", "Refinement completed successfully");
+            StreamedRefinement {
+                refinement: Some(Refinement { text: refined_text, model }),
+                typed: None,
+            }
+        }
+        Ok(refined_text) => {
+            let rest = std::mem::take(&mut typer.pending);
+            if let Err(e) = typer.type_unit(&rest) {
+                emit_log(app, "error", format!("Auto-type failed: {e}"));
+            }
+            emit_log(app, "info", "Refinement completed successfully (typed while streaming)");
+            StreamedRefinement {
+                refinement: Some(Refinement { text: refined_text, model }),
+                typed: Some(typer.typed),
+            }
+        }
+        Err(e) => {
+            let reason = typer.stop_reason.take().unwrap_or_else(|| e.to_string());
+            if typer.typed.is_empty() {
+                emit_log(app, "error", format!("Refinement failed: {reason}. Using original transcript."));
+                return StreamedRefinement::default();
+            }
+            emit_log(
+                app,
+                "error",
+                format!("Refinement failed part way: {reason}. Typing the rest of the original transcript."),
+            );
+            let rest = refine::remaining_raw(text, &typer.refined_typed).to_string();
+            if let Err(e) = typer.type_unit(&rest) {
+                emit_log(app, "error", format!("Auto-type failed: {e}"));
+            }
+            StreamedRefinement { refinement: None, typed: Some(typer.typed) }
+        }
+    }
+}
+
+/// Refinement words to receive before typing starts while the drift guard is
+/// on (fewer if the transcript is shorter), so an answer is caught before any
+/// of it is typed.
+const STREAM_HOLD_WORDS: usize = 12;
+
+/// Types a streamed refinement a sentence at a time. Spoken commands and
+/// replacement rules run on each sentence, so rules that span sentences
+/// don't match while streaming.
+struct StreamTyper<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    raw: &'a str,
+    language: Option<&'a str>,
+    app_profile: Option<&'a settings::AppProfile>,
+    injection: settings::InjectionMethod,
+    guard: bool,
+    /// Refined text received but not typed yet.
+    pending: String,
+    /// Refined text that has been typed.
+    refined_typed: String,
+    /// What was typed, after spoken commands and rules.
+    typed: String,
+    /// Why the stream was stopped, if this stopped it.
+    stop_reason: Option<String>,
+}
+
+impl<R: Runtime> StreamTyper<'_, R> {
+    /// Take a sanitized delta; returns `false` to stop the stream.
+    fn push(&mut self, delta: &str) -> bool {
+        self.pending.push_str(delta);
+        if self.guard {
+            let received = format!("{}{}", self.refined_typed, self.pending);
+            let hold = refine::word_count(self.raw).min(STREAM_HOLD_WORDS);
+            if refine::word_count(&received) < hold {
+                return true;
+            }
+            if let Some(reason) = refine::check_drift_prefix(self.raw, &received) {
+                self.stop_reason = Some(format!("output drifted from the transcript ({reason})"));
+                return false;
+            }
+        }
+
+        let Some(end) = refine::sentence_end(&self.pending) else {
+            return true;
+        };
+        let unit: String = self.pending.drain(..end).collect();
+        match self.type_unit(&unit) {
+            Ok(()) => true,
+            Err(e) => {
+                self.stop_reason = Some(format!("auto-type failed: {e}"));
+                false
+            }
+        }
+    }
+
+    /// Type one piece of text, keeping the whitespace that separates it from
+    /// what was typed before.
+    fn type_unit(&mut self, unit: &str) -> Result<(), String> {
+        let body = unit.trim_start();
+        if body.is_empty() {
+            return Ok(());
+        }
+        let mut separator = &unit[..unit.len() - body.len()];
+        if separator.is_empty()
+            && !self.typed.is_empty()
+            && !self.typed.ends_with(char::is_whitespace)
+            && !body.starts_with(chunking::is_cjk)
+        {
+            separator = " ";
+        }
+        let text = format!(
+            "{separator}{}",
+            prepare_for_typing(self.app, body, self.language, self.app_profile)
+        );
+        inject_text(self.app, &text, self.injection)?;
+        self.refined_typed.push_str(unit);
+        self.typed.push_str(&text);
+        Ok(())
+    }
+}
+
+/// Refinement parameters. Prompt, model, temperature, system message and drift
+/// guard come from the refinement preset where set, otherwise the global
+/// settings (or built-in defaults). The preset is the app profile's, else the
+/// active one.
+fn resolve_refinement_options<R: Runtime>(
+    app: &AppHandle<R>,
+    app_profile: Option<&settings::AppProfile>,
+) -> transcribe::RefinementOptions {
+    let s = settings::load(app).unwrap_or_default();
+    let preset_name = app_profile
+        .and_then(|p| p.refinement_preset.as_deref())
+        .or(s.active_refinement_preset.as_deref());
+    let preset = match preset_name {
+        Some(name) => {
+            let preset = s
+                .refinement_presets
+                .as_ref()
+                .and_then(|presets| presets.get(name))
+                .cloned();
+            if preset.is_none() {
+                emit_log(
+                    app,
+                    "warn",
+                    format!("Refinement preset '{name}' no longer exists; using global settings"),
+                );
+            }
+            preset.unwrap_or_default()
+        }
+        None => settings::RefinementPreset::default(),
+    };
+    transcribe::RefinementOptions {
+        model: preset
+            .model
+            .or(s.refinement_model)
+            .unwrap_or_else(|| DEFAULT_REFINEMENT_MODEL.to_string()),
+        prompt: preset.prompt.or(s.refinement_prompt).unwrap_or_default(),
+        temperature: preset.temperature.unwrap_or(DEFAULT_REFINEMENT_TEMPERATURE),
+        drift_guard: refine::drift_guard_enabled(
+            s.refinement_drift_guard.unwrap_or(DEFAULT_REFINEMENT_DRIFT_GUARD),
+            preset.drift_guard,
+            preset.system_message.is_some(),
+        ),
+        system_message: preset.system_message,
+    }
+}
+
+/// Run the refinement call with explicit options.
+async fn refine_with<R: Runtime>(
+    app: &AppHandle<R>,
+    text: &str,
+    options: &transcribe::RefinementOptions,
+) -> Result<String, String> {
+    // Refinement always goes through Groq, whichever provider transcribed.
+    let api_key = resolve_groq_api_key(app)
+        .ok_or_else(|| "Missing Groq API key. Set it in the app settings.".to_string())?;
+    let api = resolve_api_config(app, Some(api_key));
+    transcribe::refine_transcript(&api, text.to_string(), options)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Re-run transcription on a stored recording, optionally with another model,
+/// and attach the result to the history entry as a new variant.
+async fn do_retranscribe_history_entry<R: Runtime>(
+    app: &AppHandle<R>,
+    id: u64,
+    model: Option<String>,
+) -> Result<history::HistoryEntry, String> {
+    let entry = history::get(app, id)?;
+    let wav_path = entry
+        .wav_path
+        .map(std::path::PathBuf::from)
+        .filter(|p| p.exists())
+        .ok_or_else(|| format!("Recording for history entry {id} is no longer available"))?;
+
+    // Translated entries are re-translated so the variant stays comparable.
+    let task = if entry.translated {
+        transcribe::TranscriptionTask::Translate
+    } else {
+        transcribe::TranscriptionTask::Transcribe
+    };
+    let whisper = resolve_whisper_options(app, task, None);
+    let provider = resolve_transcription_provider(app, model.and_then(settings::non_empty), whisper)?;
+    emit_log(app, "info", format!("Re-transcribing history entry with {}...", provider.name()));
+    let transcript = provider
+        .transcribe(wav_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = apply_glossary(app, &transcript.text);
+
+    let variant = history::new_variant(
+        history::VARIANT_TRANSCRIPTION,
+        text,
+        provider.model().to_string(),
+        None,
+    );
+    let updated = history::add_variant(app, id, variant)?;
+    let _ = app.emit("history_updated", id);
+    emit_log(app, "info", "Re-transcription completed");
+    Ok(updated)
+}
+
+/// Re-run refinement on the latest transcript of a history entry, optionally
+/// with another model and/or prompt, and attach the result as a new variant.
+async fn do_rerefine_history_entry<R: Runtime>(
+    app: &AppHandle<R>,
+    id: u64,
+    model: Option<String>,
+    prompt: Option<String>,
+) -> Result<history::HistoryEntry, String> {
+    let entry = history::get(app, id)?;
+    let source = entry.latest_transcript();
+    if source.trim().is_empty() {
+        return Err("History entry has no transcript to refine. Re-transcribe it first.".into());
+    }
+
+    let mut options = resolve_refinement_options(app, None);
+    if let Some(model) = model.and_then(settings::non_empty) {
+        options.model = model;
+    }
+    if let Some(prompt) = prompt.and_then(settings::non_empty) {
+        options.prompt = prompt;
+    }
+
+    emit_log(app, "info", format!("Re-refining history entry with {}...", options.model));
+    let text = refine_with(app, source, &options).await?;
+
+    let variant = history::new_variant(
+        history::VARIANT_REFINEMENT,
+        text,
+        options.model,
+        Some(options.prompt).filter(|p| !p.is_empty()),
+    );
+    let updated = history::add_variant(app, id, variant)?;
+    let _ = app.emit("history_updated", id);
+    emit_log(app, "info", "Re-refinement completed");
+    Ok(updated)
+}
+
+#[tauri::command]
+fn start_recording(state: tauri::State<'_, AppState>, app: AppHandle) -> Result<(), String> {
+    do_start_recording(&app, state.inner())
+}
+
+#[tauri::command]
+async fn stop_and_transcribe(
+    state: tauri::State<'_, AppState>,
+    app: AppHandle,
+) -> Result<String, String> {
+    let dictation = do_stop_and_transcribe(&app, state.inner()).await?;
+    deliver_transcription(&app, &dictation);
+    Ok(dictation.text)
+}
+
+#[tauri::command]
+fn recording_status(state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    let guard = state.inner().session.lock().map_err(|e| e.to_string())?;
+    Ok(guard.is_some())
+}
+
+#[tauri::command]
+fn set_groq_api_key(app: AppHandle, api_key: String) -> Result<(), String> {
+    settings::set_groq_api_key(&app, api_key)
+}
+
+#[tauri::command]
+fn get_groq_api_key(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_groq_api_key(&app)
+}
+
+#[tauri::command]
+fn get_hotkey(app: AppHandle) -> Result<String, String> {
+    Ok(resolve_hotkey_string(&app))
+}
+
+#[tauri::command]
+fn set_hotkey(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    hotkey: String,
+) -> Result<(), String> {
+    let hotkey = hotkey.trim().to_string();
+    if hotkey.is_empty() {
+        return Err("Hotkey cannot be empty".into());
+    }
+    let translation_hotkey = settings::get_translation_hotkey(&app)?;
+    if translation_hotkey.is_some_and(|t| t.eq_ignore_ascii_case(&hotkey)) {
+        return Err("The recording hotkey must differ from the translation hotkey".into());
+    }
+    let command_hotkey = settings::get_command_hotkey(&app)?;
+    if command_hotkey.is_some_and(|c| c.eq_ignore_ascii_case(&hotkey)) {
+        return Err("The recording hotkey must differ from the command hotkey".into());
+    }
+
+    // Validate + register first, so we don't persist a broken value.
+    register_hotkey(&app, state.inner(), &hotkey)?;
+    settings::set_global_hotkey(&app, hotkey)
+}
+
+#[tauri::command]
 fn reset_hotkey(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<String, String> {
     register_hotkey(&app, state.inner(), DEFAULT_HOTKEY)?;
     settings::set_global_hotkey(&app, DEFAULT_HOTKEY.to_string())?;
     Ok(DEFAULT_HOTKEY.to_string())
 }
 
+#[tauri::command]
+fn get_translation_hotkey(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_translation_hotkey(&app)
+}
+
+/// Set the hotkey that records in translation mode; an empty string removes it.
+#[tauri::command]
+fn set_translation_hotkey(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    hotkey: String,
+) -> Result<(), String> {
+    let hotkey = hotkey.trim().to_string();
+    if hotkey.is_empty() {
+        unregister_translation_hotkey(&app, state.inner())?;
+        return settings::set_translation_hotkey(&app, hotkey);
+    }
+    if hotkey.eq_ignore_ascii_case(&resolve_hotkey_string(&app)) {
+        return Err("The translation hotkey must differ from the recording hotkey".into());
+    }
+    let command_hotkey = settings::get_command_hotkey(&app)?;
+    if command_hotkey.is_some_and(|c| c.eq_ignore_ascii_case(&hotkey)) {
+        return Err("The translation hotkey must differ from the command hotkey".into());
+    }
+
+    register_translation_hotkey(&app, state.inner(), &hotkey)?;
+    settings::set_translation_hotkey(&app, hotkey)
+}
+
+#[tauri::command]
+fn get_command_hotkey(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_command_hotkey(&app)
+}
+
+/// Set the hotkey that records in command mode; an empty string removes it.
+#[tauri::command]
+fn set_command_hotkey(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    hotkey: String,
+) -> Result<(), String> {
+    let hotkey = hotkey.trim().to_string();
+    if hotkey.is_empty() {
+        unregister_command_hotkey(&app, state.inner())?;
+        return settings::set_command_hotkey(&app, hotkey);
+    }
+    if hotkey.eq_ignore_ascii_case(&resolve_hotkey_string(&app)) {
+        return Err("The command hotkey must differ from the recording hotkey".into());
+    }
+    let translation_hotkey = settings::get_translation_hotkey(&app)?;
+    if translation_hotkey.is_some_and(|t| t.eq_ignore_ascii_case(&hotkey)) {
+        return Err("The command hotkey must differ from the translation hotkey".into());
+    }
+
+    register_command_hotkey(&app, state.inner(), &hotkey)?;
+    settings::set_command_hotkey(&app, hotkey)
+}
+
+/// "transcribe" or "translate" (English output).
+#[tauri::command]
+fn get_transcription_mode(app: AppHandle) -> Result<String, String> {
+    Ok(resolve_transcription_task(&app).as_str().to_string())
+}
+
+#[tauri::command]
+fn set_transcription_mode(app: AppHandle, mode: String) -> Result<(), String> {
+    settings::set_transcription_mode(&app, mode)
+}
+
 #[tauri::command]
 fn get_trigger_mode(app: AppHandle) -> Result<String, String> {
     Ok(resolve_trigger_mode(&app))
@@ -484,6 +1647,16 @@ fn set_type_speed_ms(app: AppHandle, ms: u64) -> Result<(), String> {
     settings::set_type_speed_ms(&app, ms)
 }
 
+#[tauri::command]
+fn get_injection_method(app: AppHandle) -> Result<settings::InjectionMethod, String> {
+    Ok(resolve_injection_method(&app, None))
+}
+
+#[tauri::command]
+fn set_injection_method(app: AppHandle, method: settings::InjectionMethod) -> Result<(), String> {
+    settings::set_injection_method(&app, method)
+}
+
 #[tauri::command]
 fn set_refine_output_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
     settings::set_refine_output_enabled(&app, enabled)
@@ -511,7 +1684,7 @@ fn set_refinement_model(app: AppHandle, model: String) -> Result<(), String> {
 
 #[tauri::command]
 fn get_refinement_model(app: AppHandle) -> Result<String, String> {
-    Ok(settings::get_refinement_model(&app)?.unwrap_or_else(|| "qwen/qwen3-32b".to_string()))
+    Ok(settings::get_refinement_model(&app)?.unwrap_or_else(|| DEFAULT_REFINEMENT_MODEL.to_string()))
 }
 
 #[tauri::command]
@@ -524,6 +1697,458 @@ fn set_mic_gain(app: AppHandle, gain: f32) -> Result<(), String> {
     settings::set_mic_gain(&app, gain)
 }
 
+#[tauri::command]
+fn get_api_base_url(app: AppHandle) -> Result<String, String> {
+    Ok(settings::get_api_base_url(&app)?
+        .unwrap_or_else(|| transcribe::GROQ_API_BASE_URL.to_string()))
+}
+
+#[tauri::command]
+fn set_api_base_url(app: AppHandle, url: String) -> Result<(), String> {
+    settings::set_api_base_url(&app, url)
+}
+
+#[tauri::command]
+fn get_transcription_model(app: AppHandle) -> Result<String, String> {
+    Ok(resolve_transcription_model(&app))
+}
+
+#[tauri::command]
+fn set_transcription_model(app: AppHandle, model: String) -> Result<(), String> {
+    settings::set_transcription_model(&app, model)
+}
+
+#[tauri::command]
+fn get_extra_headers(app: AppHandle) -> Result<std::collections::BTreeMap<String, String>, String> {
+    settings::get_extra_headers(&app)
+}
+
+#[tauri::command]
+fn set_extra_headers(
+    app: AppHandle,
+    headers: std::collections::BTreeMap<String, String>,
+) -> Result<(), String> {
+    settings::set_extra_headers(&app, headers)
+}
+
+#[tauri::command]
+fn get_upload_encoding(app: AppHandle) -> Result<String, String> {
+    Ok(resolve_upload_encoding(&app).as_str().to_string())
+}
+
+#[tauri::command]
+fn set_upload_encoding(app: AppHandle, encoding: String) -> Result<(), String> {
+    settings::set_upload_encoding(&app, encoding)
+}
+
+#[tauri::command]
+fn get_trim_silence_enabled(app: AppHandle) -> Result<bool, String> {
+    Ok(resolve_trim_silence_enabled(&app))
+}
+
+#[tauri::command]
+fn set_trim_silence_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_trim_silence_enabled(&app, enabled)
+}
+
+#[tauri::command]
+fn get_hallucination_filter_enabled(app: AppHandle) -> Result<bool, String> {
+    Ok(resolve_hallucination_filter_enabled(&app))
+}
+
+#[tauri::command]
+fn set_hallucination_filter_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_hallucination_filter_enabled(&app, enabled)
+}
+
+#[tauri::command]
+fn get_hallucination_blocklist(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(resolve_hallucination_blocklist(&app))
+}
+
+/// Replace the hallucination phrase list. `None` restores the built-in list.
+#[tauri::command]
+fn set_hallucination_blocklist(app: AppHandle, phrases: Option<Vec<String>>) -> Result<(), String> {
+    settings::set_hallucination_blocklist(&app, phrases)
+}
+
+#[tauri::command]
+fn get_max_segment_secs(app: AppHandle) -> Result<u32, String> {
+    Ok(resolve_upload_options(&app).max_segment.as_secs() as u32)
+}
+
+#[tauri::command]
+fn set_max_segment_secs(app: AppHandle, secs: u32) -> Result<(), String> {
+    settings::set_max_segment_secs(&app, secs)
+}
+
+#[tauri::command]
+fn get_transcription_language(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_transcription_language(&app)
+}
+
+/// Pin the spoken language ("en", "zh", ...); an empty string or "auto"
+/// lets Whisper detect it.
+#[tauri::command]
+fn set_transcription_language(app: AppHandle, language: String) -> Result<(), String> {
+    settings::set_transcription_language(&app, language)
+}
+
+#[tauri::command]
+fn get_transcription_prompt(app: AppHandle) -> Result<String, String> {
+    Ok(settings::get_transcription_prompt(&app)?.unwrap_or_default())
+}
+
+#[tauri::command]
+fn set_transcription_prompt(app: AppHandle, prompt: String) -> Result<(), String> {
+    settings::set_transcription_prompt(&app, prompt)
+}
+
+#[tauri::command]
+fn get_transcription_temperature(app: AppHandle) -> Result<Option<f32>, String> {
+    settings::get_transcription_temperature(&app)
+}
+
+#[tauri::command]
+fn set_transcription_temperature(app: AppHandle, temperature: Option<f32>) -> Result<(), String> {
+    settings::set_transcription_temperature(&app, temperature)
+}
+
+#[tauri::command]
+fn list_transcription_profiles(
+    app: AppHandle,
+) -> Result<std::collections::BTreeMap<String, settings::TranscriptionProfile>, String> {
+    settings::get_transcription_profiles(&app)
+}
+
+#[tauri::command]
+fn save_transcription_profile(
+    app: AppHandle,
+    name: String,
+    profile: settings::TranscriptionProfile,
+) -> Result<(), String> {
+    settings::save_transcription_profile(&app, name, profile)
+}
+
+#[tauri::command]
+fn delete_transcription_profile(app: AppHandle, name: String) -> Result<(), String> {
+    settings::delete_transcription_profile(&app, name)
+}
+
+#[tauri::command]
+fn get_active_transcription_profile(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_active_transcription_profile(&app)
+}
+
+/// Select the transcription profile whose overrides apply; `None` clears it.
+#[tauri::command]
+fn set_active_transcription_profile(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    settings::set_active_transcription_profile(&app, name)
+}
+
+#[tauri::command]
+fn list_refinement_presets(
+    app: AppHandle,
+) -> Result<std::collections::BTreeMap<String, settings::RefinementPreset>, String> {
+    settings::get_refinement_presets(&app)
+}
+
+#[tauri::command]
+fn save_refinement_preset(
+    app: AppHandle,
+    name: String,
+    preset: settings::RefinementPreset,
+) -> Result<(), String> {
+    settings::save_refinement_preset(&app, name, preset)?;
+    refresh_preset_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_refinement_preset(app: AppHandle, name: String) -> Result<(), String> {
+    settings::delete_refinement_preset(&app, name)?;
+    refresh_preset_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_active_refinement_preset(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_active_refinement_preset(&app)
+}
+
+/// Select the refinement preset whose settings apply; `None` clears it.
+#[tauri::command]
+fn set_active_refinement_preset(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    settings::set_active_refinement_preset(&app, name)?;
+    refresh_preset_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn list_app_profiles(
+    app: AppHandle,
+) -> Result<std::collections::BTreeMap<String, settings::AppProfile>, String> {
+    settings::get_app_profiles(&app)
+}
+
+#[tauri::command]
+fn save_app_profile(app: AppHandle, app_id: String, profile: settings::AppProfile) -> Result<(), String> {
+    settings::save_app_profile(&app, app_id, profile)
+}
+
+#[tauri::command]
+fn delete_app_profile(app: AppHandle, app_id: String) -> Result<(), String> {
+    settings::delete_app_profile(&app, app_id)
+}
+
+/// The app that had focus when the last recording started, to offer
+/// creating a profile for it.
+#[tauri::command]
+fn get_last_target_app(state: tauri::State<'_, AppState>) -> Result<Option<platform::AppIdentity>, String> {
+    Ok(state.target_app.lock().map_err(|e| e.to_string())?.clone())
+}
+
+#[tauri::command]
+fn list_glossary(app: AppHandle) -> Result<Vec<glossary::GlossaryEntry>, String> {
+    settings::get_glossary(&app)
+}
+
+/// Add or update a glossary entry; `previous_term` renames an existing one.
+#[tauri::command]
+fn save_glossary_entry(
+    app: AppHandle,
+    entry: glossary::GlossaryEntry,
+    previous_term: Option<String>,
+) -> Result<(), String> {
+    settings::save_glossary_entry(&app, entry, previous_term)
+}
+
+#[tauri::command]
+fn delete_glossary_entry(app: AppHandle, term: String) -> Result<(), String> {
+    settings::delete_glossary_entry(&app, term)
+}
+
+/// Import glossary entries from CSV text (`term,variants`, variants separated
+/// by `;`). Returns the number of entries imported.
+#[tauri::command]
+fn import_glossary_csv(app: AppHandle, csv: String, replace_all: bool) -> Result<usize, String> {
+    let entries = glossary::from_csv(&csv).map_err(|e| format!("{e:#}"))?;
+    settings::import_glossary(&app, entries, replace_all)
+}
+
+#[tauri::command]
+fn export_glossary_csv(app: AppHandle) -> Result<String, String> {
+    glossary::to_csv(&settings::get_glossary(&app)?).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_spoken_commands_enabled(app: AppHandle) -> Result<bool, String> {
+    Ok(resolve_spoken_commands_enabled(&app))
+}
+
+#[tauri::command]
+fn set_spoken_commands_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_spoken_commands_enabled(&app, enabled)
+}
+
+#[tauri::command]
+fn get_refinement_drift_guard(app: AppHandle) -> Result<bool, String> {
+    Ok(resolve_refinement_drift_guard(&app))
+}
+
+#[tauri::command]
+fn set_refinement_drift_guard(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_refinement_drift_guard(&app, enabled)
+}
+
+#[tauri::command]
+fn get_refinement_streaming(app: AppHandle) -> Result<bool, String> {
+    Ok(resolve_refinement_streaming(&app))
+}
+
+#[tauri::command]
+fn set_refinement_streaming(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set_refinement_streaming(&app, enabled)
+}
+
+#[tauri::command]
+fn list_replacement_rules(app: AppHandle) -> Result<Vec<rules::ReplacementRule>, String> {
+    settings::get_replacement_rules(&app)
+}
+
+/// Add (`id` 0) or update a replacement rule; returns it with its id.
+#[tauri::command]
+fn save_replacement_rule(
+    app: AppHandle,
+    rule: rules::ReplacementRule,
+) -> Result<rules::ReplacementRule, String> {
+    settings::save_replacement_rule(&app, rule)
+}
+
+#[tauri::command]
+fn delete_replacement_rule(app: AppHandle, id: u64) -> Result<(), String> {
+    settings::delete_replacement_rule(&app, id)
+}
+
+#[tauri::command]
+fn set_replacement_rule_enabled(app: AppHandle, id: u64, enabled: bool) -> Result<(), String> {
+    settings::set_replacement_rule_enabled(&app, id, enabled)
+}
+
+#[tauri::command]
+fn reorder_replacement_rules(app: AppHandle, ids: Vec<u64>) -> Result<(), String> {
+    settings::reorder_replacement_rules(&app, ids)
+}
+
+/// Show what a single (possibly unsaved or disabled) rule does to `text`.
+#[tauri::command]
+fn preview_replacement_rule(rule: rules::ReplacementRule, text: String) -> Result<String, String> {
+    rules::apply_rule(&rule, &text)
+        .map(|t| t.into_owned())
+        .map_err(|e| e.to_string())
+}
+
+/// Show what all enabled rules together do to `text`.
+#[tauri::command]
+fn preview_replacement_rules(app: AppHandle, text: String) -> Result<rules::Outcome, String> {
+    Ok(rules::apply(&settings::get_replacement_rules(&app)?, &text))
+}
+
+/// Encodings this build can upload ("opus" only with the `opus` feature).
+#[tauri::command]
+fn list_upload_encodings() -> Vec<&'static str> {
+    encode::UploadEncoding::available()
+}
+
+#[tauri::command]
+fn list_input_devices() -> Result<Vec<audio::InputDeviceInfo>, String> {
+    audio::list_input_devices().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_input_device(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_input_device(&app)
+}
+
+#[tauri::command]
+fn set_input_device(app: AppHandle, name: String) -> Result<(), String> {
+    settings::set_input_device(&app, name)
+}
+
+#[tauri::command]
+fn get_transcription_provider(app: AppHandle) -> Result<String, String> {
+    Ok(resolve_transcription_provider_name(&app))
+}
+
+#[tauri::command]
+fn set_transcription_provider(app: AppHandle, provider: String) -> Result<(), String> {
+    settings::set_transcription_provider(&app, provider)
+}
+
+#[tauri::command]
+fn get_openai_compatible_base_url(app: AppHandle) -> Result<String, String> {
+    Ok(settings::get_openai_compatible_base_url(&app)?.unwrap_or_default())
+}
+
+#[tauri::command]
+fn set_openai_compatible_base_url(app: AppHandle, url: String) -> Result<(), String> {
+    settings::set_openai_compatible_base_url(&app, url)
+}
+
+#[tauri::command]
+fn get_openai_compatible_model(app: AppHandle) -> Result<String, String> {
+    Ok(settings::get_openai_compatible_model(&app)?
+        .unwrap_or_else(|| transcribe::GROQ_TRANSCRIPTION_MODEL.to_string()))
+}
+
+#[tauri::command]
+fn set_openai_compatible_model(app: AppHandle, model: String) -> Result<(), String> {
+    settings::set_openai_compatible_model(&app, model)
+}
+
+#[tauri::command]
+fn get_openai_compatible_api_key(app: AppHandle) -> Result<Option<String>, String> {
+    settings::get_openai_compatible_api_key(&app)
+}
+
+#[tauri::command]
+fn set_openai_compatible_api_key(app: AppHandle, api_key: String) -> Result<(), String> {
+    settings::set_openai_compatible_api_key(&app, api_key)
+}
+
+#[derive(Serialize)]
+struct LocalCommandConfig {
+    command: String,
+    args: Vec<String>,
+}
+
+#[tauri::command]
+fn get_local_command(app: AppHandle) -> Result<LocalCommandConfig, String> {
+    let (command, args) = settings::get_local_command(&app)?;
+    Ok(LocalCommandConfig {
+        command: command.unwrap_or_default(),
+        args,
+    })
+}
+
+#[tauri::command]
+fn set_local_command(app: AppHandle, command: String, args: Vec<String>) -> Result<(), String> {
+    settings::set_local_command(&app, command, args)
+}
+
+#[tauri::command]
+fn list_history(app: AppHandle) -> Result<Vec<history::HistoryEntry>, String> {
+    history::list(&app)
+}
+
+#[tauri::command]
+fn search_history(app: AppHandle, query: String) -> Result<Vec<history::HistoryEntry>, String> {
+    history::search(&app, &query)
+}
+
+#[tauri::command]
+fn delete_history_entry(app: AppHandle, id: u64) -> Result<(), String> {
+    history::delete(&app, id)
+}
+
+#[tauri::command]
+fn clear_history(app: AppHandle) -> Result<(), String> {
+    history::clear(&app)
+}
+
+#[tauri::command]
+fn copy_history_entry(app: AppHandle, id: u64) -> Result<(), String> {
+    let entry = history::get(&app, id)?;
+    set_clipboard_text(entry.final_text())
+}
+
+#[tauri::command]
+fn type_history_entry(app: AppHandle, id: u64) -> Result<(), String> {
+    let entry = history::get(&app, id)?;
+    let language = if entry.translated { Some("en") } else { entry.language.as_deref() };
+    let text = prepare_for_typing(&app, entry.final_text(), language, None);
+    inject_text(&app, &text, resolve_injection_method(&app, None))
+}
+
+#[tauri::command]
+async fn retranscribe_history_entry(
+    app: AppHandle,
+    id: u64,
+    model: Option<String>,
+) -> Result<history::HistoryEntry, String> {
+    do_retranscribe_history_entry(&app, id, model).await
+}
+
+#[tauri::command]
+async fn rerefine_history_entry(
+    app: AppHandle,
+    id: u64,
+    model: Option<String>,
+    prompt: Option<String>,
+) -> Result<history::HistoryEntry, String> {
+    do_rerefine_history_entry(&app, id, model, prompt).await
+}
+
 #[tauri::command]
 fn write_clipboard(text: String) -> Result<(), String> {
     set_clipboard_text(&text)
@@ -574,51 +2199,35 @@ fn enable_fn_key_listening(
                 tauri::async_runtime::spawn(async move {
                     let state = app_clone.state::<AppState>();
                     match do_stop_and_transcribe(&app_clone, state.inner()).await {
-                        Ok(text) => {
-                            let _ = app_clone.emit("transcription", text.clone());
-                            if resolve_auto_type_enabled(&app_clone) {
-                                if let Err(e) = type_text_into_focused_app(&app_clone, &text) {
-                                    emit_log(&app_clone, "error", format!("Auto-type failed: {e}"));
-                                }
-                            }
-                        }
+                        Ok(dictation) => deliver_transcription(&app_clone, &dictation),
                         Err(e) => emit_log(&app_clone, "error", format!("Stop failed: {e}")),
                     }
                 });
             }
-        } else if mode == "toggle" {
-            if !pressed {
-                // Toggle on Fn release
-                let app_clone = app.clone();
-                tauri::async_runtime::spawn(async move {
-                    let state = app_clone.state::<AppState>();
-                    let is_recording = state
-                        .inner()
-                        .session
-                        .lock()
-                        .ok()
-                        .map(|g| g.is_some())
-                        .unwrap_or(false);
-
-                    if !is_recording {
-                        if let Err(e) = do_start_recording(&app_clone, state.inner()) {
-                            emit_log(&app_clone, "error", format!("Failed to start: {e}"));
-                        }
-                    } else {
-                        match do_stop_and_transcribe(&app_clone, state.inner()).await {
-                            Ok(text) => {
-                                let _ = app_clone.emit("transcription", text.clone());
-                                if resolve_auto_type_enabled(&app_clone) {
-                                    if let Err(e) = type_text_into_focused_app(&app_clone, &text) {
-                                        emit_log(&app_clone, "error", format!("Auto-type failed: {e}"));
-                                    }
-                                }
-                            }
-                            Err(e) => emit_log(&app_clone, "error", format!("Stop failed: {e}")),
-                        }
+        } else if mode == "toggle" && !pressed {
+            // Toggle on Fn release
+            let app_clone = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_clone.state::<AppState>();
+                let is_recording = state
+                    .inner()
+                    .session
+                    .lock()
+                    .ok()
+                    .map(|g| g.is_some())
+                    .unwrap_or(false);
+
+                if !is_recording {
+                    if let Err(e) = do_start_recording(&app_clone, state.inner()) {
+                        emit_log(&app_clone, "error", format!("Failed to start: {e}"));
                     }
-                });
-            }
+                } else {
+                    match do_stop_and_transcribe(&app_clone, state.inner()).await {
+                        Ok(dictation) => deliver_transcription(&app_clone, &dictation),
+                        Err(e) => emit_log(&app_clone, "error", format!("Stop failed: {e}")),
+                    }
+                }
+            });
         }
     })?;
 
@@ -643,6 +2252,62 @@ fn disable_fn_key_listening(
     Ok(())
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Tray: refinement presets
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Menu ids of the preset items are this prefix plus the preset name; the
+/// bare prefix is the "no preset" item.
+const PRESET_MENU_PREFIX: &str = "preset:";
+
+/// The tray's "Refinement preset" submenu, rebuilt whenever presets change.
+struct PresetMenu(Submenu<tauri::Wry>);
+
+/// Rebuild the preset submenu from settings, checking the active preset.
+fn refresh_preset_menu(app: &AppHandle) {
+    let Some(menu) = app.try_state::<PresetMenu>() else {
+        return;
+    };
+    if let Err(e) = fill_preset_menu(app, &menu.0) {
+        emit_log(app, "error", format!("Failed to update tray presets: {e}"));
+    }
+}
+
+fn fill_preset_menu(app: &AppHandle, menu: &Submenu<tauri::Wry>) -> tauri::Result<()> {
+    for item in menu.items()? {
+        menu.remove(&item)?;
+    }
+    let presets = settings::get_refinement_presets(app).unwrap_or_default();
+    let active = settings::get_active_refinement_preset(app).ok().flatten();
+
+    let none_item = CheckMenuItemBuilder::with_id(PRESET_MENU_PREFIX, "None (global settings)")
+        .checked(active.is_none())
+        .build(app)?;
+    menu.append(&none_item)?;
+    for name in presets.keys() {
+        let item = CheckMenuItemBuilder::with_id(format!("{PRESET_MENU_PREFIX}{name}"), name)
+            .checked(active.as_deref() == Some(name.as_str()))
+            .build(app)?;
+        menu.append(&item)?;
+    }
+    Ok(())
+}
+
+/// Handle a click on a preset item.
+fn select_preset_from_menu(app: &AppHandle, name: &str) {
+    let name = Some(name.to_string()).filter(|n| !n.is_empty());
+    match settings::set_active_refinement_preset(app, name.clone()) {
+        Ok(()) => emit_log(
+            app,
+            "info",
+            format!("Refinement preset: {}", name.as_deref().unwrap_or("none")),
+        ),
+        Err(e) => emit_log(app, "error", e),
+    }
+    // Also resets the check mark the OS toggled on click.
+    refresh_preset_menu(app);
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -653,9 +2318,25 @@ pub fn run() {
             let app_handle = app.handle().clone();
             let state = app_handle.state::<AppState>();
 
+            // Surface API retries / rate-limit waits in the logs panel.
+            let log_handle = app_handle.clone();
+            http_client::set_retry_logger(move |message| emit_log(&log_handle, "warn", message));
+
             let hotkey_str = resolve_hotkey_string(&app_handle);
             register_hotkey(&app_handle, state.inner(), &hotkey_str)?;
 
+            // A broken translation hotkey shouldn't keep the app from starting.
+            if let Some(hotkey) = settings::get_translation_hotkey(&app_handle).ok().flatten() {
+                if let Err(e) = register_translation_hotkey(&app_handle, state.inner(), &hotkey) {
+                    emit_log(&app_handle, "error", e);
+                }
+            }
+            if let Some(hotkey) = settings::get_command_hotkey(&app_handle).ok().flatten() {
+                if let Err(e) = register_command_hotkey(&app_handle, state.inner(), &hotkey) {
+                    emit_log(&app_handle, "error", e);
+                }
+            }
+
             // ---- Tray menu with Tauri v2 API ----
             let show_item = MenuItemBuilder::with_id("show", "Show GroqBara").build(app)?;
             let hide_item = MenuItemBuilder::with_id("hide", "Hide").build(app)?;
@@ -677,6 +2358,14 @@ pub fn run() {
                 .checked(mode == "toggle")
                 .build(app)?;
 
+            let translate_item = CheckMenuItemBuilder::with_id("translate", "Translate to English")
+                .checked(resolve_transcription_task(&app_handle) == transcribe::TranscriptionTask::Translate)
+                .build(app)?;
+
+            let preset_menu = SubmenuBuilder::with_id(app, "presets", "Refinement preset").build()?;
+            fill_preset_menu(&app_handle, &preset_menu)?;
+            app.manage(PresetMenu(preset_menu.clone()));
+
             let sep3 = PredefinedMenuItem::separator(app)?;
             let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
 
@@ -690,6 +2379,8 @@ pub fn run() {
                 .item(&auto_type_item)
                 .item(&hold_item)
                 .item(&toggle_item)
+                .item(&translate_item)
+                .item(&preset_menu)
                 .item(&sep3)
                 .item(&quit_item)
                 .build()?;
@@ -728,12 +2419,7 @@ pub fn run() {
                             tauri::async_runtime::spawn(async move {
                                 let state = app_h.state::<AppState>();
                                 match do_stop_and_transcribe(&app_h, state.inner()).await {
-                                    Ok(text) => {
-                                        let _ = app_h.emit("transcription", text.clone());
-                                        if resolve_auto_type_enabled(&app_h) {
-                                            let _ = type_text_into_focused_app(&app_h, &text);
-                                        }
-                                    }
+                                    Ok(dictation) => deliver_transcription(&app_h, &dictation),
                                     Err(e) => emit_log(&app_h, "error", format!("Stop failed: {e}")),
                                 }
                             });
@@ -749,6 +2435,29 @@ pub fn run() {
                         "mode_toggle" => {
                             let _ = settings::set_trigger_mode(&app_handle2, "toggle".into());
                         }
+                        "translate" => {
+                            // Flip the stored mode and sync the check mark to it, in case
+                            // the mode was changed from the main window in the meantime.
+                            let translate = resolve_transcription_task(&app_handle2)
+                                != transcribe::TranscriptionTask::Translate;
+                            let task = if translate {
+                                transcribe::TranscriptionTask::Translate
+                            } else {
+                                transcribe::TranscriptionTask::Transcribe
+                            };
+                            match settings::set_transcription_mode(&app_handle2, task.as_str().into()) {
+                                Ok(()) => emit_log(
+                                    &app_handle2,
+                                    "info",
+                                    format!("Transcription mode: {}", task.as_str()),
+                                ),
+                                Err(e) => emit_log(&app_handle2, "error", e),
+                            }
+                            let _ = translate_item.set_checked(translate);
+                        }
+                        id if id.starts_with(PRESET_MENU_PREFIX) => {
+                            select_preset_from_menu(app, &id[PRESET_MENU_PREFIX.len()..]);
+                        }
                         _ => {}
                     }
                 })
@@ -789,6 +2498,7 @@ pub fn run() {
 
             // Windows: transparent + no shadow → pill floats with no border.
             // macOS: transparent requires private API, so use a solid dark window instead.
+            // Linux: transparency depends on the compositor, so use the solid window too.
             #[cfg(target_os = "windows")]
             {
                 overlay_builder = overlay_builder
@@ -796,7 +2506,7 @@ pub fn run() {
                     .transparent(true)
                     .shadow(false);
             }
-            #[cfg(target_os = "macos")]
+            #[cfg(any(target_os = "macos", target_os = "linux"))]
             {
                 overlay_builder = overlay_builder
                     .inner_size(200.0, 40.0);
@@ -806,7 +2516,7 @@ pub fn run() {
                 Ok(w) => {
                     #[cfg(target_os = "windows")]
                     let (win_w, win_h) = (300.0, 60.0);
-                    #[cfg(target_os = "macos")]
+                    #[cfg(any(target_os = "macos", target_os = "linux"))]
                     let (win_w, win_h) = (200.0, 40.0);
 
                     if let Ok(Some(monitor)) = w.primary_monitor() {
@@ -835,12 +2545,20 @@ pub fn run() {
             get_hotkey,
             set_hotkey,
             reset_hotkey,
+            get_translation_hotkey,
+            set_translation_hotkey,
+            get_command_hotkey,
+            set_command_hotkey,
+            get_transcription_mode,
+            set_transcription_mode,
             get_trigger_mode,
             set_trigger_mode,
             get_auto_type_enabled,
             set_auto_type_enabled,
             get_type_speed_ms,
             set_type_speed_ms,
+            get_injection_method,
+            set_injection_method,
             set_refine_output_enabled,
             get_refine_output_enabled,
             set_refinement_prompt,
@@ -849,6 +2567,82 @@ pub fn run() {
             get_refinement_model,
             get_mic_gain,
             set_mic_gain,
+            list_input_devices,
+            get_input_device,
+            set_input_device,
+            get_api_base_url,
+            set_api_base_url,
+            get_transcription_model,
+            set_transcription_model,
+            get_extra_headers,
+            set_extra_headers,
+            get_upload_encoding,
+            set_upload_encoding,
+            list_upload_encodings,
+            get_max_segment_secs,
+            set_max_segment_secs,
+            get_trim_silence_enabled,
+            set_trim_silence_enabled,
+            get_hallucination_filter_enabled,
+            set_hallucination_filter_enabled,
+            get_hallucination_blocklist,
+            set_hallucination_blocklist,
+            get_transcription_language,
+            set_transcription_language,
+            get_transcription_prompt,
+            set_transcription_prompt,
+            get_transcription_temperature,
+            set_transcription_temperature,
+            list_transcription_profiles,
+            save_transcription_profile,
+            delete_transcription_profile,
+            get_active_transcription_profile,
+            set_active_transcription_profile,
+            list_refinement_presets,
+            save_refinement_preset,
+            delete_refinement_preset,
+            get_active_refinement_preset,
+            set_active_refinement_preset,
+            list_app_profiles,
+            save_app_profile,
+            delete_app_profile,
+            get_last_target_app,
+            list_glossary,
+            save_glossary_entry,
+            delete_glossary_entry,
+            import_glossary_csv,
+            export_glossary_csv,
+            get_spoken_commands_enabled,
+            set_spoken_commands_enabled,
+            get_refinement_drift_guard,
+            set_refinement_drift_guard,
+            get_refinement_streaming,
+            set_refinement_streaming,
+            list_replacement_rules,
+            save_replacement_rule,
+            delete_replacement_rule,
+            set_replacement_rule_enabled,
+            reorder_replacement_rules,
+            preview_replacement_rule,
+            preview_replacement_rules,
+            get_transcription_provider,
+            set_transcription_provider,
+            get_openai_compatible_base_url,
+            set_openai_compatible_base_url,
+            get_openai_compatible_model,
+            set_openai_compatible_model,
+            get_openai_compatible_api_key,
+            set_openai_compatible_api_key,
+            get_local_command,
+            set_local_command,
+            list_history,
+            search_history,
+            delete_history_entry,
+            clear_history,
+            copy_history_entry,
+            type_history_entry,
+            retranscribe_history_entry,
+            rerefine_history_entry,
             write_clipboard,
             type_text,
             accessibility_status,
@@ -0,0 +1,498 @@
+//! Linux platform implementation.
+//!
+//! Uses:
+//! - Read access to `/dev/input/event*` plus an X display with the XTest
+//!   extension as the equivalent of an accessibility check
+//! - `evdev` for trigger-key listening (works under both X11 and Wayland,
+//!   but requires the user to be in the `input` group)
+//! - `XTest` for text injection and the copy/paste shortcuts (X11 / XWayland)
+//! - `crate::audio` (cpal/ALSA) for audio capture
+
+use super::{AppIdentity, KeyCallback, KeyListenerHandle, Platform, RecordingHandle};
+use crate::audio::{CaptureOptions, RecordingSession};
+use crate::http_client;
+use std::ffi::CStr;
+use std::fs;
+use std::os::raw::{c_char, c_int, c_uchar, c_ulong};
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use evdev::{InputEventKind, Key};
+use x11::{keysym, xlib, xtest};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Platform Implementation
+// ─────────────────────────────────────────────────────────────────────────────
+
+pub struct LinuxPlatform;
+
+impl Platform for LinuxPlatform {
+    fn is_accessibility_trusted(&self) -> bool {
+        // Linux has no central permission prompt. What matters is whether the
+        // process can read raw input devices for the trigger-key listener
+        // (granted via the `input` group or udev rules), and whether there is
+        // an X display with XTest to type into.
+        input_devices_readable() && xtest_available()
+    }
+
+    fn request_accessibility_permission(&self) -> bool {
+        // Nothing to prompt for: access is granted by adding the user to the
+        // `input` group (and logging in again).
+        self.is_accessibility_trusted()
+    }
+
+    fn start_fn_key_listener(
+        &self,
+        callback: KeyCallback,
+    ) -> Result<Box<dyn KeyListenerHandle>, String> {
+        let listener = FnKeyListenerImpl::new(callback)?;
+        Ok(Box::new(listener))
+    }
+
+    fn type_text(&self, text: &str, per_chunk_delay: Duration) -> Result<(), String> {
+        type_text_impl(text, per_chunk_delay)
+    }
+
+    fn press_paste_shortcut(&self) -> Result<(), String> {
+        press_ctrl_key_impl(keysym::XK_v)
+    }
+
+    fn press_copy_shortcut(&self) -> Result<(), String> {
+        press_ctrl_key_impl(keysym::XK_c)
+    }
+
+    fn focused_app(&self) -> Option<AppIdentity> {
+        focused_app_impl()
+    }
+
+    fn start_audio_capture(
+        &self,
+        options: CaptureOptions,
+    ) -> Result<Box<dyn RecordingHandle>, String> {
+        let session = RecordingSession::start(options).map_err(|e| e.to_string())?;
+        Ok(Box::new(session))
+    }
+
+    fn name(&self) -> &'static str {
+        "linux"
+    }
+}
+
+fn input_devices_readable() -> bool {
+    let Ok(entries) = fs::read_dir("/dev/input") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        entry.file_name().to_string_lossy().starts_with("event")
+            && fs::File::open(entry.path()).is_ok()
+    })
+}
+
+fn xtest_available() -> bool {
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            return false;
+        }
+        let (mut event_base, mut error_base, mut major, mut minor) = (0, 0, 0, 0);
+        let supported = xtest::XTestQueryExtension(
+            display,
+            &mut event_base,
+            &mut error_base,
+            &mut major,
+            &mut minor,
+        ) != 0;
+        xlib::XCloseDisplay(display);
+        supported
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Fn Key Listener (evdev)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Default trigger keys on Linux.
+///
+/// As on Windows, real Fn keys are handled by the keyboard firmware and never
+/// reach the kernel, so **Right Alt** is the "Fn-equivalent" trigger. F24 is
+/// supported as an alternative for users who remap a spare key to it.
+const DEFAULT_TRIGGER_KEYS: [Key; 2] = [Key::KEY_RIGHTALT, Key::KEY_F24];
+
+/// How long `poll` waits before re-checking the stop flag.
+const POLL_TIMEOUT_MS: i32 = 100;
+
+pub struct FnKeyListenerImpl {
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl FnKeyListenerImpl {
+    pub fn new(mut callback: KeyCallback) -> Result<Self, String> {
+        // Watch every device that can emit one of the trigger keys
+        // (built-in keyboard, USB keyboards, remappers, ...).
+        let mut devices: Vec<evdev::Device> = evdev::enumerate()
+            .map(|(_, device)| device)
+            .filter(|device| {
+                device.supported_keys().is_some_and(|keys| {
+                    DEFAULT_TRIGGER_KEYS.iter().any(|&k| keys.contains(k))
+                })
+            })
+            .collect();
+
+        if devices.is_empty() {
+            return Err("No readable keyboard devices found in /dev/input. \
+                Add your user to the 'input' group and log in again:\n\
+                \n    sudo usermod -aG input $USER"
+                .into());
+        }
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop_flag_thread = stop_flag.clone();
+
+        thread::spawn(move || {
+            let mut pressed = false;
+
+            while !stop_flag_thread.load(Ordering::SeqCst) {
+                let mut fds: Vec<libc::pollfd> = devices
+                    .iter()
+                    .map(|d| libc::pollfd {
+                        fd: d.as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    })
+                    .collect();
+
+                let ready = unsafe {
+                    libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, POLL_TIMEOUT_MS)
+                };
+                if ready < 0 {
+                    let err = std::io::Error::last_os_error();
+                    if err.kind() == std::io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    http_client::log_warning(&format!(
+                        "Trigger key listener stopped: poll() on input devices failed: {err}"
+                    ));
+                    break;
+                }
+                if ready == 0 {
+                    continue;
+                }
+
+                // Unplugged devices report POLLHUP/POLLERR (or ENODEV on
+                // read) from then on, which would make poll() return at once
+                // forever. Drop them.
+                let mut gone = vec![false; devices.len()];
+                let polled = devices.iter_mut().zip(fds.iter()).zip(gone.iter_mut());
+                for ((device, pfd), gone) in polled {
+                    if pfd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+                        *gone = true;
+                        continue;
+                    }
+                    if pfd.revents & libc::POLLIN == 0 {
+                        continue;
+                    }
+                    let events = match device.fetch_events() {
+                        Ok(events) => events,
+                        Err(e) => {
+                            *gone = e.raw_os_error() == Some(libc::ENODEV);
+                            continue;
+                        }
+                    };
+                    for event in events {
+                        let InputEventKind::Key(key) = event.kind() else {
+                            continue;
+                        };
+                        if !DEFAULT_TRIGGER_KEYS.contains(&key) {
+                            continue;
+                        }
+                        // 1 = press, 0 = release, 2 = autorepeat (ignored).
+                        let now_pressed = match event.value() {
+                            1 => true,
+                            0 => false,
+                            _ => continue,
+                        };
+                        if now_pressed != pressed {
+                            pressed = now_pressed;
+                            callback(pressed);
+                        }
+                    }
+                }
+
+                if gone.contains(&true) {
+                    let mut gone = gone.into_iter();
+                    devices.retain(|device| {
+                        let keep = !gone.next().unwrap_or(false);
+                        if !keep {
+                            http_client::log_warning(&format!(
+                                "Input device '{}' disconnected; no longer listening to it",
+                                device.name().unwrap_or("unknown")
+                            ));
+                        }
+                        keep
+                    });
+                    if devices.is_empty() {
+                        http_client::log_warning(
+                            "Trigger key listener stopped: all keyboard devices were disconnected. \
+                             Turn trigger-key listening off and on again after reconnecting one.",
+                        );
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { stop_flag })
+    }
+}
+
+impl KeyListenerHandle for FnKeyListenerImpl {
+    fn stop(&self) {
+        // The listener thread notices within POLL_TIMEOUT_MS and drops the devices.
+        self.stop_flag.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for FnKeyListenerImpl {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Text Injection (XTest)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Map a character to the X keysym that produces it.
+///
+/// Latin-1 keysyms equal their code points; everything else uses the
+/// `0x01000000 + codepoint` Unicode keysym range.
+fn char_to_keysym(c: char) -> Option<xlib::KeySym> {
+    let keysym = match c {
+        '\n' | '\r' => keysym::XK_Return as xlib::KeySym,
+        '\t' => keysym::XK_Tab as xlib::KeySym,
+        c if c.is_control() => return None,
+        c if (0x20..=0x7e).contains(&(c as u32)) || (0xa0..=0xff).contains(&(c as u32)) => {
+            c as xlib::KeySym
+        }
+        c => 0x0100_0000 | c as xlib::KeySym,
+    };
+    Some(keysym)
+}
+
+/// Find a keycode with no keysyms bound that we can temporarily remap.
+unsafe fn find_scratch_keycode(display: *mut xlib::Display) -> Option<xlib::KeyCode> {
+    let (mut min, mut max) = (0, 0);
+    xlib::XDisplayKeycodes(display, &mut min, &mut max);
+
+    let count = max - min + 1;
+    let mut per_keycode = 0;
+    let mapping = xlib::XGetKeyboardMapping(display, min as xlib::KeyCode, count, &mut per_keycode);
+    if mapping.is_null() {
+        return None;
+    }
+
+    let syms = std::slice::from_raw_parts(mapping, (count * per_keycode) as usize);
+    let scratch = (0..count)
+        .rev()
+        .find(|&i| {
+            let start = (i * per_keycode) as usize;
+            syms[start..start + per_keycode as usize].iter().all(|&s| s == 0)
+        })
+        .map(|i| (min + i) as xlib::KeyCode);
+
+    xlib::XFree(mapping as *mut _);
+    scratch
+}
+
+unsafe fn press_keysym(display: *mut xlib::Display, keycode: xlib::KeyCode, sym: xlib::KeySym) {
+    // Bind the keysym to both the plain and shifted level so modifier state
+    // cannot change what gets typed.
+    let mut syms = [sym, sym];
+    xlib::XChangeKeyboardMapping(display, keycode as i32, 2, syms.as_mut_ptr(), 1);
+    // Wait for the server to apply the remap (and queue its MappingNotify)
+    // before the key event, or the old keysym can be typed.
+    xlib::XSync(display, xlib::False);
+
+    xtest::XTestFakeKeyEvent(display, keycode as u32, xlib::True, 0);
+    xtest::XTestFakeKeyEvent(display, keycode as u32, xlib::False, 0);
+    xlib::XSync(display, xlib::False);
+}
+
+/// Types text into the currently focused app via the XTest extension.
+///
+/// Notes:
+/// - Each character is bound to a spare keycode and "pressed", which works
+///   for arbitrary Unicode regardless of the active keyboard layout.
+/// - Only works on X11 or for XWayland clients under Wayland.
+/// - Chunks the stream so `per_chunk_delay` matches the other platforms.
+fn type_text_impl(text: &str, per_chunk_delay: Duration) -> Result<(), String> {
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            return Err("Failed to open X display. Text injection requires X11 or XWayland (is DISPLAY set?)".into());
+        }
+
+        let Some(keycode) = find_scratch_keycode(display) else {
+            xlib::XCloseDisplay(display);
+            return Err("No spare keycode available for text injection".into());
+        };
+
+        // Pause after every 24 characters. macOS/Windows chunk by 24 UTF-16
+        // units, which differs only for characters outside the BMP (emoji).
+        const CHUNK_CHARS: usize = 24;
+
+        let chars: Vec<char> = text.chars().collect();
+        for chunk in chars.chunks(CHUNK_CHARS) {
+            for &c in chunk {
+                if let Some(sym) = char_to_keysym(c) {
+                    press_keysym(display, keycode, sym);
+                }
+            }
+
+            if per_chunk_delay.as_millis() > 0 {
+                std::thread::sleep(per_chunk_delay);
+            }
+        }
+
+        // Restore the scratch keycode to unbound.
+        let mut empty = [0 as xlib::KeySym, 0];
+        xlib::XChangeKeyboardMapping(display, keycode as i32, 2, empty.as_mut_ptr(), 1);
+        xlib::XSync(display, xlib::False);
+        xlib::XCloseDisplay(display);
+    }
+
+    Ok(())
+}
+
+/// Presses Ctrl+`key` (a keysym on the current layout) via XTest.
+fn press_ctrl_key_impl(key: u32) -> Result<(), String> {
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            return Err("Failed to open X display. Text injection requires X11 or XWayland (is DISPLAY set?)".into());
+        }
+
+        let control = xlib::XKeysymToKeycode(display, keysym::XK_Control_L as xlib::KeySym);
+        let keycode = xlib::XKeysymToKeycode(display, key as xlib::KeySym);
+        if control == 0 || keycode == 0 {
+            xlib::XCloseDisplay(display);
+            return Err("Shortcut keys are not mapped on this keyboard layout".into());
+        }
+
+        xtest::XTestFakeKeyEvent(display, control as u32, xlib::True, 0);
+        xtest::XTestFakeKeyEvent(display, keycode as u32, xlib::True, 0);
+        xtest::XTestFakeKeyEvent(display, keycode as u32, xlib::False, 0);
+        xtest::XTestFakeKeyEvent(display, control as u32, xlib::False, 0);
+        xlib::XSync(display, xlib::False);
+        xlib::XCloseDisplay(display);
+    }
+
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Focused Application (EWMH)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Finds the active window via `_NET_ACTIVE_WINDOW` and names its app after
+/// the process (`_NET_WM_PID` → `/proc/<pid>/comm`), falling back to the
+/// `WM_CLASS` instance name for windows that don't report a PID.
+///
+/// Like text injection, this only sees X11 and XWayland windows.
+fn focused_app_impl() -> Option<AppIdentity> {
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        let root = xlib::XDefaultRootWindow(display);
+        let identity = cardinal_property(display, root, c"_NET_ACTIVE_WINDOW")
+            .filter(|&window| window != 0)
+            .and_then(|window| window_identity(display, window as xlib::Window));
+        xlib::XCloseDisplay(display);
+        identity
+    }
+}
+
+unsafe fn window_identity(display: *mut xlib::Display, window: xlib::Window) -> Option<AppIdentity> {
+    let class = class_hint(display, window);
+    let process = cardinal_property(display, window, c"_NET_WM_PID")
+        .and_then(|pid| fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+        .map(|comm| comm.trim().to_string())
+        .filter(|comm| !comm.is_empty());
+    let (instance, class) = match class {
+        Some((instance, class)) => (Some(instance), Some(class)),
+        None => (None, None),
+    };
+    Some(AppIdentity {
+        id: process.or(instance)?,
+        name: class,
+    })
+}
+
+/// First item of a 32-bit window property (a window id, PID, ...).
+unsafe fn cardinal_property(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    name: &CStr,
+) -> Option<u64> {
+    let atom = xlib::XInternAtom(display, name.as_ptr(), xlib::True);
+    if atom == 0 {
+        return None;
+    }
+    let mut actual_type: xlib::Atom = 0;
+    let mut actual_format: c_int = 0;
+    let mut n_items: c_ulong = 0;
+    let mut bytes_after: c_ulong = 0;
+    let mut data: *mut c_uchar = std::ptr::null_mut();
+    let status = xlib::XGetWindowProperty(
+        display,
+        window,
+        atom,
+        0,
+        1,
+        xlib::False,
+        xlib::AnyPropertyType as xlib::Atom,
+        &mut actual_type,
+        &mut actual_format,
+        &mut n_items,
+        &mut bytes_after,
+        &mut data,
+    );
+    if status != xlib::Success as c_int || data.is_null() {
+        return None;
+    }
+    // Xlib returns 32-bit items as C longs.
+    let value = (n_items > 0 && actual_format == 32).then(|| *(data as *const c_ulong) as u64);
+    xlib::XFree(data.cast());
+    value
+}
+
+/// `WM_CLASS` as (instance, class), e.g. ("code", "Code").
+unsafe fn class_hint(display: *mut xlib::Display, window: xlib::Window) -> Option<(String, String)> {
+    let mut hint = xlib::XClassHint {
+        res_name: std::ptr::null_mut(),
+        res_class: std::ptr::null_mut(),
+    };
+    if xlib::XGetClassHint(display, window, &mut hint) == 0 {
+        return None;
+    }
+    let take = |ptr: *mut c_char| {
+        if ptr.is_null() {
+            return String::new();
+        }
+        let value = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+        xlib::XFree(ptr.cast());
+        value
+    };
+    let instance = take(hint.res_name);
+    let class = take(hint.res_class);
+    Some((instance, class)).filter(|(instance, _)| !instance.is_empty())
+}
@@ -3,19 +3,17 @@
 //! Uses:
 //! - `AXIsProcessTrusted` for accessibility checks
 //! - `CGEventTap` for Fn key listening
-//! - `CGEventCreateKeyboardEvent` for text injection
-//! - `cpal` for audio capture (cross-platform, but configured here)
-
-use super::{KeyCallback, KeyListenerHandle, Platform, RecordingHandle};
-use std::ffi::c_void;
-use std::path::PathBuf;
-use std::sync::{mpsc, Arc, Mutex};
-use std::thread;
+//! - `CGEventCreateKeyboardEvent` for text injection and the copy/paste shortcuts
+//! - `NSWorkspace.frontmostApplication` (via the Objective-C runtime) for the
+//!   focused app
+//! - `crate::audio` (cpal/CoreAudio) for audio capture
+
+use super::{AppIdentity, KeyCallback, KeyListenerHandle, Platform, RecordingHandle};
+use crate::audio::{CaptureOptions, RecordingSession};
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use anyhow::{anyhow, Context, Result as AnyhowResult};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-
 // ─────────────────────────────────────────────────────────────────────────────
 // Platform Implementation
 // ─────────────────────────────────────────────────────────────────────────────
@@ -86,8 +84,23 @@ impl Platform for MacOSPlatform {
         type_text_impl(text, per_chunk_delay)
     }
 
-    fn start_audio_capture(&self, gain: f32) -> Result<Box<dyn RecordingHandle>, String> {
-        let session = RecordingSession::start(gain).map_err(|e| e.to_string())?;
+    fn press_paste_shortcut(&self) -> Result<(), String> {
+        press_command_key_impl(K_VK_ANSI_V)
+    }
+
+    fn press_copy_shortcut(&self) -> Result<(), String> {
+        press_command_key_impl(K_VK_ANSI_C)
+    }
+
+    fn focused_app(&self) -> Option<AppIdentity> {
+        focused_app_impl()
+    }
+
+    fn start_audio_capture(
+        &self,
+        options: CaptureOptions,
+    ) -> Result<Box<dyn RecordingHandle>, String> {
+        let session = RecordingSession::start(options).map_err(|e| e.to_string())?;
         Ok(Box::new(session))
     }
 
@@ -281,10 +294,16 @@ extern "C" {
         stringLength: usize,
         unicodeString: *const u16,
     );
+    fn CGEventSetFlags(event: *mut c_void, flags: u64);
     fn CGEventPost(tap: u32, event: *mut c_void);
     fn CFRelease(cf: *const c_void);
 }
 
+/// `kVK_ANSI_C` / `kVK_ANSI_V` from `HIToolbox/Events.h`.
+const K_VK_ANSI_C: u16 = 0x08;
+const K_VK_ANSI_V: u16 = 0x09;
+const K_CG_EVENT_FLAG_MASK_COMMAND: u64 = 0x0010_0000;
+
 fn post_unicode_chunk(chunk: &[u16]) {
     unsafe {
         // virtualKey is ignored when Unicode string is set.
@@ -335,145 +354,65 @@ fn type_text_impl(text: &str, per_chunk_delay: Duration) -> Result<(), String> {
     Ok(())
 }
 
+/// Posts Cmd+`virtual_key` as a key down/up pair with the Command flag set.
+fn press_command_key_impl(virtual_key: u16) -> Result<(), String> {
+    unsafe {
+        for key_down in [true, false] {
+            let event = CGEventCreateKeyboardEvent(std::ptr::null(), virtual_key, key_down);
+            if event.is_null() {
+                return Err("Failed to create keyboard event".into());
+            }
+            CGEventSetFlags(event, K_CG_EVENT_FLAG_MASK_COMMAND);
+            CGEventPost(K_CG_HID_EVENT_TAP, event);
+            CFRelease(event);
+        }
+    }
+    Ok(())
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
-// Audio Capture (cpal-based, same as before)
+// Focused Application (NSWorkspace)
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// A Send handle for an in-progress recording.
-///
-/// The actual `cpal::Stream` is created and owned inside a dedicated thread
-/// because `cpal::Stream` is not `Send`/`Sync` on macOS.
-struct RecordingSession {
-    stop_tx: mpsc::Sender<()>,
-    done_rx: mpsc::Receiver<AnyhowResult<PathBuf>>,
+#[link(name = "objc", kind = "dylib")]
+extern "C" {
+    fn objc_getClass(name: *const c_char) -> *mut c_void;
+    fn sel_registerName(name: *const c_char) -> *mut c_void;
+    fn objc_msgSend();
+    fn objc_autoreleasePoolPush() -> *mut c_void;
+    fn objc_autoreleasePoolPop(pool: *mut c_void);
 }
 
-impl RecordingSession {
-    fn start(gain: f32) -> AnyhowResult<Self> {
-        let (stop_tx, stop_rx) = mpsc::channel::<()>();
-        let (done_tx, done_rx) = mpsc::channel::<AnyhowResult<PathBuf>>();
-
-        thread::spawn(move || {
-            let res = (|| -> AnyhowResult<PathBuf> {
-                let host = cpal::default_host();
-                let device = host
-                    .default_input_device()
-                    .ok_or_else(|| anyhow!("No default input device"))?;
-
-                let supported_config = device
-                    .default_input_config()
-                    .context("Failed to get default input config")?;
-
-                let sample_rate = supported_config.sample_rate().0;
-                let channels = supported_config.channels();
-
-                let samples: Arc<Mutex<Vec<i16>>> = Arc::new(Mutex::new(Vec::new()));
-                let samples_cb = samples.clone();
-
-                let err_fn = |err| eprintln!("an error occurred on the input audio stream: {err}");
-
-                let stream = match supported_config.sample_format() {
-                    cpal::SampleFormat::I16 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[i16], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let amplified = (s as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
-                                        buf.push(amplified);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    cpal::SampleFormat::U16 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[u16], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let f = (s as f32 / 32768.0 - 1.0) * gain;
-                                        let v: i16 = (f.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-                                        buf.push(v);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    cpal::SampleFormat::F32 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[f32], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let amplified = s * gain;
-                                        let v: i16 = (amplified.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-                                        buf.push(v);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    other => return Err(anyhow!("Unsupported sample format: {other:?}")),
-                };
-
-                stream.play()?;
-
-                // Block until stop signal.
-                let _ = stop_rx.recv();
-                drop(stream);
-
-                // Write WAV.
-                let samples = samples
-                    .lock()
-                    .map_err(|_| anyhow!("Failed to lock samples"))?;
-
-                let mut path = std::env::temp_dir();
-                let filename = format!(
-                    "groqtranscriber-{}.wav",
-                    chrono::Utc::now().format("%Y%m%d-%H%M%S")
-                );
-                path.push(filename);
-
-                let spec = hound::WavSpec {
-                    channels,
-                    sample_rate,
-                    bits_per_sample: 16,
-                    sample_format: hound::SampleFormat::Int,
-                };
-
-                let mut writer =
-                    hound::WavWriter::create(&path, spec).context("Failed to create wav")?;
-                for &s in samples.iter() {
-                    writer.write_sample(s).ok();
-                }
-                writer.finalize().ok();
-
-                Ok(path)
-            })();
-
-            let _ = done_tx.send(res);
-        });
+/// Send an Objective-C message that takes no arguments and returns an object
+/// (or C string) pointer. Messages to nil return nil.
+unsafe fn send(receiver: *mut c_void, selector: &CStr) -> *mut c_void {
+    if receiver.is_null() {
+        return std::ptr::null_mut();
+    }
+    let msg_send: unsafe extern "C" fn(*mut c_void, *mut c_void) -> *mut c_void =
+        std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    msg_send(receiver, sel_registerName(selector.as_ptr()))
+}
 
-        Ok(Self { stop_tx, done_rx })
+unsafe fn ns_string(string: *mut c_void) -> Option<String> {
+    let utf8 = send(string, c"UTF8String") as *const c_char;
+    if utf8.is_null() {
+        return None;
     }
+    Some(CStr::from_ptr(utf8).to_string_lossy().into_owned()).filter(|s| !s.is_empty())
 }
 
-impl RecordingHandle for RecordingSession {
-    fn stop_and_save_wav(self: Box<Self>) -> Result<PathBuf, String> {
-        let _ = self.stop_tx.send(());
-        self.done_rx
-            .recv()
-            .map_err(|_| "Recording thread terminated unexpectedly".to_string())?
-            .map_err(|e| e.to_string())
+/// `NSWorkspace.sharedWorkspace.frontmostApplication`, identified by its
+/// bundle ID (or executable name for apps without one).
+fn focused_app_impl() -> Option<AppIdentity> {
+    unsafe {
+        let pool = objc_autoreleasePoolPush();
+        let workspace = send(objc_getClass(c"NSWorkspace".as_ptr()), c"sharedWorkspace");
+        let app = send(workspace, c"frontmostApplication");
+        let name = ns_string(send(app, c"localizedName"));
+        let id = ns_string(send(app, c"bundleIdentifier"))
+            .or_else(|| ns_string(send(send(app, c"executableURL"), c"lastPathComponent")));
+        objc_autoreleasePoolPop(pool);
+        id.map(|id| AppIdentity { id, name })
     }
 }
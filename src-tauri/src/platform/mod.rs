@@ -4,24 +4,30 @@
 //! - **Hotkey registration** (global shortcuts beyond what tauri-plugin-global-shortcut provides)
 //! - **Key hold/toggle trigger detection** (e.g., Fn key on macOS)
 //! - **Audio capture** (microphone recording)
-//! - **Text injection** (auto-type into focused applications)
+//! - **Text injection** (auto-type or paste into focused applications)
+//! - **Focused application** (which app text will be typed into)
 //!
 //! # Architecture
 //!
 //! The [`Platform`] trait defines the interface. Platform-specific implementations
-//! live in submodules (`macos`, `windows`), selected at compile time via `#[cfg]`.
+//! live in submodules (`macos`, `windows`, `linux`), selected at compile time via `#[cfg]`.
 //!
 //! The [`current()`] function returns a boxed trait object for the current platform.
 
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::audio::CaptureOptions;
+
 #[cfg(target_os = "macos")]
 mod macos;
 
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(target_os = "linux")]
+mod linux;
+
 // Re-export the FnKeyListener for compatibility with existing code
 #[cfg(target_os = "macos")]
 pub use macos::FnKeyListenerImpl;
@@ -29,6 +35,9 @@ pub use macos::FnKeyListenerImpl;
 #[cfg(target_os = "windows")]
 pub use windows::FnKeyListenerImpl;
 
+#[cfg(target_os = "linux")]
+pub use linux::FnKeyListenerImpl;
+
 /// Callback type for key state changes (pressed/released).
 pub type KeyCallback = Box<dyn FnMut(bool) + Send + 'static>;
 
@@ -45,6 +54,16 @@ pub trait RecordingHandle: Send {
     fn stop_and_save_wav(self: Box<Self>) -> Result<PathBuf, String>;
 }
 
+/// Identity of an application, as used to look up per-app profiles.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AppIdentity {
+    /// Bundle ID on macOS (e.g. "com.tinyspeck.slackmacgap"); executable name
+    /// without extension on Windows and Linux (e.g. "slack").
+    pub id: String,
+    /// Display name, where the OS provides one.
+    pub name: Option<String>,
+}
+
 /// Platform abstraction trait.
 ///
 /// Implementations provide OS-specific functionality for:
@@ -52,6 +71,7 @@ pub trait RecordingHandle: Send {
 /// - Fn-key (or equivalent) listening
 /// - Text injection (auto-type)
 /// - Audio capture
+/// - Focused application lookup
 ///
 /// # Example
 ///
@@ -71,7 +91,7 @@ pub trait Platform: Send + Sync {
     fn is_accessibility_trusted(&self) -> bool;
 
     /// Request accessibility permission from the user.
-    /// On macOS, this shows the system prompt. On Windows, this may be a no-op.
+    /// On macOS, this shows the system prompt. On Windows and Linux, this may be a no-op.
     /// Returns true if permission is already granted.
     fn request_accessibility_permission(&self) -> bool;
 
@@ -103,21 +123,46 @@ pub trait Platform: Send + Sync {
     /// * `per_chunk_delay` - Delay between chunks of characters (for rate limiting)
     fn type_text(&self, text: &str, per_chunk_delay: Duration) -> Result<(), String>;
 
+    /// Press the platform's paste shortcut (Cmd+V on macOS, Ctrl+V
+    /// elsewhere) in the focused application.
+    ///
+    /// Used to inject text through the clipboard. Requires accessibility
+    /// permissions on macOS.
+    fn press_paste_shortcut(&self) -> Result<(), String>;
+
+    /// Press the platform's copy shortcut (Cmd+C on macOS, Ctrl+C
+    /// elsewhere) in the focused application.
+    ///
+    /// Used to read the selected text in command mode.
+    fn press_copy_shortcut(&self) -> Result<(), String>;
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Focused Application
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Identify the application that has keyboard focus.
+    ///
+    /// Returns `None` if it can't be determined (e.g. no X display on Linux).
+    fn focused_app(&self) -> Option<AppIdentity>;
+
     // ─────────────────────────────────────────────────────────────────────────
     // Audio Capture
     // ─────────────────────────────────────────────────────────────────────────
 
-    /// Start capturing audio from the default input device.
+    /// Start capturing audio from the input device selected in `options`
+    /// (gain, device name). Platforms may add their own quirks (e.g. warm-up).
     ///
-    /// `gain` is a multiplier applied to samples (1.0 = no change, >1.0 = louder).
     /// Returns a handle that can be used to stop recording and save to WAV.
-    fn start_audio_capture(&self, gain: f32) -> Result<Box<dyn RecordingHandle>, String>;
+    fn start_audio_capture(
+        &self,
+        options: CaptureOptions,
+    ) -> Result<Box<dyn RecordingHandle>, String>;
 
     // ─────────────────────────────────────────────────────────────────────────
     // Platform Info
     // ─────────────────────────────────────────────────────────────────────────
 
-    /// Return the platform name (e.g., "macos", "windows").
+    /// Return the platform name (e.g., "macos", "windows", "linux").
     fn name(&self) -> &'static str;
 }
 
@@ -141,9 +186,14 @@ pub fn current() -> Box<dyn Platform> {
     Box::new(windows::WindowsPlatform)
 }
 
-#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+#[cfg(target_os = "linux")]
+pub fn current() -> Box<dyn Platform> {
+    Box::new(linux::LinuxPlatform)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
 pub fn current() -> Box<dyn Platform> {
-    compile_error!("Unsupported platform. Only macOS and Windows are supported.");
+    compile_error!("Unsupported platform. Only macOS, Windows and Linux are supported.");
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
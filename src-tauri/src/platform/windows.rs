@@ -10,25 +10,31 @@
 //!   (`WH_KEYBOARD_LL`) that watches a *configurable* trigger key
 //!   (default: Right Alt; F24 is supported as an alternative).
 //! - `type_text` uses `SendInput` with `KEYEVENTF_UNICODE` so we can inject
-//!   arbitrary Unicode text into the focused app.
-//! - `start_audio_capture` reuses the same `cpal`-based WAV recording
-//!   pipeline as macOS.
-
-use super::{KeyCallback, KeyListenerHandle, Platform, RecordingHandle};
-use std::path::PathBuf;
+//!   arbitrary Unicode text into the focused app; the paste and copy
+//!   shortcuts send Ctrl+V / Ctrl+C the same way.
+//! - `start_audio_capture` uses the shared `crate::audio` pipeline with a
+//!   short warm-up, since WASAPI drops the first buffers after opening.
+//! - `focused_app` names the foreground window's process by its executable.
+
+use super::{AppIdentity, KeyCallback, KeyListenerHandle, Platform, RecordingHandle};
+use crate::audio::{CaptureOptions, RecordingSession};
+use std::path::Path;
 use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 
-use anyhow::{anyhow, Context, Result as AnyhowResult};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
-    VIRTUAL_KEY, VK_F24, VK_RMENU,
+    VIRTUAL_KEY, VK_C, VK_CONTROL, VK_F24, VK_RMENU, VK_V,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallNextHookEx, DispatchMessageW, GetMessageW, PostThreadMessageW, SetWindowsHookExW,
+    CallNextHookEx, DispatchMessageW, GetForegroundWindow, GetMessageW, GetWindowThreadProcessId,
+    PostThreadMessageW, SetWindowsHookExW,
     TranslateMessage, HC_ACTION, KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN,
     WM_KEYUP, WM_QUIT, WM_SYSKEYDOWN, WM_SYSKEYUP,
 };
@@ -65,8 +71,30 @@ impl Platform for WindowsPlatform {
         type_text_impl(text, per_chunk_delay)
     }
 
-    fn start_audio_capture(&self, gain: f32) -> Result<Box<dyn RecordingHandle>, String> {
-        let session = RecordingSession::start(gain).map_err(|e| e.to_string())?;
+    fn press_paste_shortcut(&self) -> Result<(), String> {
+        press_ctrl_key_impl(VK_V)
+    }
+
+    fn press_copy_shortcut(&self) -> Result<(), String> {
+        press_ctrl_key_impl(VK_C)
+    }
+
+    fn focused_app(&self) -> Option<AppIdentity> {
+        focused_app_impl()
+    }
+
+    fn start_audio_capture(
+        &self,
+        options: CaptureOptions,
+    ) -> Result<Box<dyn RecordingHandle>, String> {
+        // Give the Windows audio subsystem a moment to initialize the
+        // capture pipeline. Without this, the first recording after
+        // app launch may capture zero samples.
+        let options = CaptureOptions {
+            warm_up: Some(Duration::from_millis(150)),
+            ..options
+        };
+        let session = RecordingSession::start(options).map_err(|e| e.to_string())?;
         Ok(Box::new(session))
     }
 
@@ -256,174 +284,68 @@ fn type_text_impl(text: &str, per_chunk_delay: Duration) -> Result<(), String> {
     Ok(())
 }
 
-// ─────────────────────────────────────────────────────────────────────────────
-// Audio Capture (cpal-based, same pipeline as macOS)
-// ─────────────────────────────────────────────────────────────────────────────
-
-/// A Send handle for an in-progress recording.
-///
-/// The actual `cpal::Stream` is created and owned inside a dedicated thread
-/// because `cpal::Stream` is not `Send`/`Sync` on all platforms.
-struct RecordingSession {
-    stop_tx: mpsc::Sender<()>,
-    done_rx: mpsc::Receiver<AnyhowResult<PathBuf>>,
+fn key_input(key: VIRTUAL_KEY, up: bool) -> INPUT {
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: key,
+                wScan: 0,
+                dwFlags: if up { KEYEVENTF_KEYUP } else { Default::default() },
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
 }
 
-impl RecordingSession {
-    fn start(gain: f32) -> AnyhowResult<Self> {
-        let (stop_tx, stop_rx) = mpsc::channel::<()>();
-        let (done_tx, done_rx) = mpsc::channel::<AnyhowResult<PathBuf>>();
-        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
-
-        thread::spawn(move || {
-            // Init phase: open device, build stream, play, warm up.
-            // If any of this fails, signal error via ready_tx and exit.
-            let init = (|| -> AnyhowResult<(cpal::Stream, Arc<Mutex<Vec<i16>>>, u32, u16)> {
-                let host = cpal::default_host();
-                let device = host
-                    .default_input_device()
-                    .ok_or_else(|| anyhow!("No default input device"))?;
-
-                let supported_config = device
-                    .default_input_config()
-                    .context("Failed to get default input config")?;
-
-                let sample_rate = supported_config.sample_rate().0;
-                let channels = supported_config.channels();
-
-                let samples: Arc<Mutex<Vec<i16>>> = Arc::new(Mutex::new(Vec::new()));
-                let samples_cb = samples.clone();
-
-                let err_fn = |err| eprintln!("an error occurred on the input audio stream: {err}");
-
-                let stream = match supported_config.sample_format() {
-                    cpal::SampleFormat::I16 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[i16], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let amplified = (s as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
-                                        buf.push(amplified);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    cpal::SampleFormat::U16 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[u16], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let f = (s as f32 / 32768.0 - 1.0) * gain;
-                                        let v: i16 = (f.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-                                        buf.push(v);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    cpal::SampleFormat::F32 => {
-                        let config: cpal::StreamConfig = supported_config.into();
-                        device.build_input_stream(
-                            &config,
-                            move |data: &[f32], _| {
-                                if let Ok(mut buf) = samples_cb.lock() {
-                                    for &s in data {
-                                        let amplified = s * gain;
-                                        let v: i16 = (amplified.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-                                        buf.push(v);
-                                    }
-                                }
-                            },
-                            err_fn,
-                            None,
-                        )?
-                    }
-                    other => return Err(anyhow!("Unsupported sample format: {other:?}")),
-                };
-
-                stream.play()?;
-
-                // Give Windows audio subsystem a moment to initialize the
-                // capture pipeline. Without this, the first recording after
-                // app launch may capture zero samples.
-                thread::sleep(Duration::from_millis(150));
-
-                Ok((stream, samples, sample_rate, channels))
-            })();
-
-            match init {
-                Err(e) => {
-                    let _ = ready_tx.send(Err(e.to_string()));
-                    let _ = done_tx.send(Err(e));
-                }
-                Ok((stream, samples, sample_rate, channels)) => {
-                    // Audio is now actively capturing — signal the caller.
-                    let _ = ready_tx.send(Ok(()));
-
-                    // Block until stop signal.
-                    let _ = stop_rx.recv();
-                    drop(stream);
-
-                    let res = (|| -> AnyhowResult<PathBuf> {
-                        let samples = samples
-                            .lock()
-                            .map_err(|_| anyhow!("Failed to lock samples"))?;
-
-                        let mut path = std::env::temp_dir();
-                        let filename = format!(
-                            "groqtranscriber-{}.wav",
-                            chrono::Utc::now().format("%Y%m%d-%H%M%S")
-                        );
-                        path.push(filename);
-
-                        let spec = hound::WavSpec {
-                            channels,
-                            sample_rate,
-                            bits_per_sample: 16,
-                            sample_format: hound::SampleFormat::Int,
-                        };
-
-                        let mut writer =
-                            hound::WavWriter::create(&path, spec).context("Failed to create wav")?;
-                        for &s in samples.iter() {
-                            writer.write_sample(s).ok();
-                        }
-                        writer.finalize().ok();
-
-                        Ok(path)
-                    })();
-
-                    let _ = done_tx.send(res);
-                }
-            }
-        });
-
-        // Block until audio is actually capturing, so the caller
-        // knows it's safe to show "Recording" to the user.
-        ready_rx
-            .recv()
-            .map_err(|_| anyhow!("Recording thread terminated during init"))?
-            .map_err(|e| anyhow!("{e}"))?;
-
-        Ok(Self { stop_tx, done_rx })
+/// Presses Ctrl+`key` in a single `SendInput` call.
+fn press_ctrl_key_impl(key: VIRTUAL_KEY) -> Result<(), String> {
+    let inputs = [
+        key_input(VK_CONTROL, false),
+        key_input(key, false),
+        key_input(key, true),
+        key_input(VK_CONTROL, true),
+    ];
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err("SendInput failed while pressing a shortcut".into());
     }
+    Ok(())
 }
 
-impl RecordingHandle for RecordingSession {
-    fn stop_and_save_wav(self: Box<Self>) -> Result<PathBuf, String> {
-        let _ = self.stop_tx.send(());
-        self.done_rx
-            .recv()
-            .map_err(|_| "Recording thread terminated unexpectedly".to_string())?
-            .map_err(|e| e.to_string())
+// ─────────────────────────────────────────────────────────────────────────────
+// Focused Application (GetForegroundWindow)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The foreground window's process, identified by its executable name
+/// without `.exe` (e.g. "slack", "Code").
+fn focused_app_impl() -> Option<AppIdentity> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buf[..len as usize]);
+        let id = Path::new(&path).file_stem()?.to_string_lossy().into_owned();
+        Some(AppIdentity { id, name: None })
     }
 }
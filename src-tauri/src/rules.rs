@@ -0,0 +1,199 @@
+//! Text replacement and snippet expansion rules.
+//!
+//! Rules run in order on the final text just before it is typed, so a spoken
+//! trigger like "insert signature" can expand into a multi-line signature.
+//! A rule matches either a literal phrase (case-insensitive, whole words) or
+//! a regular expression whose capture groups can be used in the replacement
+//! as `$1` or `${name}`.
+
+use std::borrow::Cow;
+
+use anyhow::{anyhow, Result};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// The pattern is a phrase, matched ignoring case at word boundaries.
+    /// The replacement is inserted as-is.
+    #[default]
+    Literal,
+    /// The pattern is a regular expression; `$1`/`${name}` in the
+    /// replacement refer to its capture groups.
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplacementRule {
+    /// Assigned when the rule is first saved.
+    #[serde(default)]
+    pub id: u64,
+    /// Optional label shown in the UI.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: MatchKind,
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ReplacementRule {
+    /// Compile the rule's pattern, reporting an invalid regex.
+    pub fn compile(&self) -> Result<Regex> {
+        if self.pattern.trim().is_empty() {
+            return Err(anyhow!("Pattern must not be empty"));
+        }
+        match self.kind {
+            MatchKind::Literal => {
+                let phrase = self.pattern.trim();
+                let mut pattern = regex::escape(phrase);
+                // Only anchor to word boundaries where the phrase starts or
+                // ends with a word character; "\b" next to "." never matches.
+                if phrase.starts_with(is_word_char) {
+                    pattern.insert_str(0, r"\b");
+                }
+                if phrase.ends_with(is_word_char) {
+                    pattern.push_str(r"\b");
+                }
+                Ok(RegexBuilder::new(&pattern).case_insensitive(true).build()?)
+            }
+            MatchKind::Regex => {
+                Regex::new(&self.pattern).map_err(|e| anyhow!("Invalid regular expression: {e}"))
+            }
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Result of applying rules to a piece of text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    pub text: String,
+    /// Ids of the rules that changed the text, in the order they ran.
+    pub applied: Vec<u64>,
+    /// Rules skipped because their pattern doesn't compile: `(id, error)`.
+    pub errors: Vec<(u64, String)>,
+}
+
+/// Apply every enabled rule to `text`, in order. Each rule sees the output
+/// of the previous one.
+pub fn apply(rules: &[ReplacementRule], text: &str) -> Outcome {
+    let mut outcome = Outcome {
+        text: text.to_string(),
+        applied: Vec::new(),
+        errors: Vec::new(),
+    };
+    for rule in rules.iter().filter(|r| r.enabled) {
+        match apply_rule(rule, &outcome.text) {
+            Ok(Cow::Owned(changed)) => {
+                outcome.text = changed;
+                outcome.applied.push(rule.id);
+            }
+            Ok(Cow::Borrowed(_)) => {}
+            Err(e) => outcome.errors.push((rule.id, e.to_string())),
+        }
+    }
+    outcome
+}
+
+/// Apply one rule, whether or not it is enabled. Returns `Cow::Borrowed`
+/// when nothing matched.
+pub fn apply_rule<'t>(rule: &ReplacementRule, text: &'t str) -> Result<Cow<'t, str>> {
+    let pattern = rule.compile()?;
+    Ok(match rule.kind {
+        MatchKind::Literal => pattern.replace_all(text, NoExpand(&rule.replacement)),
+        MatchKind::Regex => pattern.replace_all(text, rule.replacement.as_str()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: u64, kind: MatchKind, pattern: &str, replacement: &str) -> ReplacementRule {
+        ReplacementRule {
+            id,
+            name: String::new(),
+            kind,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn literal_expands_snippets_ignoring_case() {
+        let rules = vec![rule(
+            1,
+            MatchKind::Literal,
+            "insert signature",
+            "Best,\nAda",
+        )];
+        let outcome = apply(&rules, "Thanks for the update. Insert signature.");
+        assert_eq!(outcome.text, "Thanks for the update. Best,\nAda.");
+        assert_eq!(outcome.applied, vec![1]);
+    }
+
+    #[test]
+    fn literal_matches_whole_words_and_inserts_dollars_verbatim() {
+        let rules = vec![rule(1, MatchKind::Literal, "cost", "$1 fee")];
+        assert_eq!(apply(&rules, "cost vs costume").text, "$1 fee vs costume");
+        let dotted = vec![rule(2, MatchKind::Literal, "e.g.", "for example")];
+        assert_eq!(
+            apply(&dotted, "Fruit, e.g. apples").text,
+            "Fruit, for example apples"
+        );
+    }
+
+    #[test]
+    fn regex_uses_capture_groups() {
+        let rules = vec![
+            rule(1, MatchKind::Regex, r"(?i)ticket (\d+)", "JIRA-$1"),
+            rule(
+                2,
+                MatchKind::Regex,
+                r"(?P<user>\w+) at example dot com",
+                "${user}@example.com",
+            ),
+        ];
+        let outcome = apply(&rules, "Ticket 42 is assigned to ada at example dot com");
+        assert_eq!(outcome.text, "JIRA-42 is assigned to ada@example.com");
+        assert_eq!(outcome.applied, vec![1, 2]);
+    }
+
+    #[test]
+    fn rules_run_in_order_and_disabled_rules_are_skipped() {
+        let mut rules = vec![
+            rule(1, MatchKind::Literal, "my address", "1 Main St"),
+            rule(2, MatchKind::Regex, r"Main St", "Main Street"),
+            rule(3, MatchKind::Literal, "Street", "Road"),
+        ];
+        rules[2].enabled = false;
+        let outcome = apply(&rules, "Ship it to my address");
+        assert_eq!(outcome.text, "Ship it to 1 Main Street");
+        assert_eq!(outcome.applied, vec![1, 2]);
+    }
+
+    #[test]
+    fn invalid_regex_is_reported_and_skipped() {
+        let rules = vec![
+            rule(1, MatchKind::Regex, r"(unclosed", "x"),
+            rule(2, MatchKind::Literal, "hello", "hi"),
+        ];
+        let outcome = apply(&rules, "hello");
+        assert_eq!(outcome.text, "hi");
+        assert_eq!(outcome.errors.len(), 1);
+        assert_eq!(outcome.errors[0].0, 1);
+    }
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{collections::BTreeMap, fs, path::PathBuf};
 use tauri::{AppHandle, Manager, Runtime};
 
+use crate::glossary::GlossaryEntry;
+use crate::rules::ReplacementRule;
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct Settings {
     #[serde(default)]
@@ -27,6 +30,10 @@ pub struct Settings {
     #[serde(default)]
     pub type_speed_ms: Option<u64>,
 
+    /// How auto-typed text gets into the focused app (default: type)
+    #[serde(default)]
+    pub injection_method: Option<InjectionMethod>,
+
     /// Whether we should refine the output using Qwen model
     #[serde(default)]
     pub refine_output_enabled: Option<bool>,
@@ -43,12 +50,239 @@ pub struct Settings {
     #[serde(default)]
     pub mic_gain: Option<f32>,
 
+    /// Name of the input device to record from (None = system default)
+    #[serde(default)]
+    pub input_device: Option<String>,
+
+    /// API root used for both the Whisper and chat calls
+    /// (default: "https://api.groq.com/openai/v1"). Point this at a gateway
+    /// or a local mock server.
+    #[serde(default)]
+    pub api_base_url: Option<String>,
+
+    /// Whisper model used by the Groq provider (default: "whisper-large-v3")
+    #[serde(default)]
+    pub transcription_model: Option<String>,
+
+    /// Extra HTTP headers sent with every API request (name -> value)
+    #[serde(default)]
+    pub extra_headers: Option<BTreeMap<String, String>>,
+
+    /// Which speech-to-text backend to use:
+    /// - "groq": Groq-hosted Whisper (default)
+    /// - "openai_compatible": any OpenAI-compatible `/audio/transcriptions` server
+    /// - "local_command": pipe the WAV into a local executable (e.g. whisper.cpp)
+    #[serde(default)]
+    pub transcription_provider: Option<String>,
+
+    /// API root for the OpenAI-compatible provider, e.g. "http://localhost:8000/v1"
+    #[serde(default)]
+    pub openai_compatible_base_url: Option<String>,
+
+    /// Model name sent to the OpenAI-compatible provider
+    #[serde(default)]
+    pub openai_compatible_model: Option<String>,
+
+    /// Optional bearer token for the OpenAI-compatible provider
+    #[serde(default)]
+    pub openai_compatible_api_key: Option<String>,
+
+    /// Executable run by the local command provider
+    #[serde(default)]
+    pub local_command: Option<String>,
+
+    /// Arguments for the local command; "{wav}" is replaced with the WAV path
+    #[serde(default)]
+    pub local_command_args: Option<Vec<String>>,
+
+    /// Audio format uploaded to the transcription API:
+    /// - "wav": 16-bit PCM (default)
+    /// - "flac": lossless, roughly half the size
+    /// - "opus": Ogg/Opus, much smaller (only in builds with the `opus` feature)
+    #[serde(default)]
+    pub upload_encoding: Option<String>,
+
+    /// Longest audio segment sent in one transcription request, in seconds
+    /// (default: 120). Longer recordings are split at quiet points.
+    #[serde(default)]
+    pub max_segment_secs: Option<u32>,
+
+    /// Trim leading/trailing silence before upload and skip recordings with
+    /// no speech (default: true)
+    #[serde(default)]
+    pub trim_silence_enabled: Option<bool>,
+
+    /// Drop transcripts that look like Whisper hallucinations (default: true)
+    #[serde(default)]
+    pub hallucination_filter_enabled: Option<bool>,
+
+    /// Phrases treated as hallucinations when they are the whole transcript
+    /// (None = built-in list)
+    #[serde(default)]
+    pub hallucination_blocklist: Option<Vec<String>>,
+
+    /// Spoken language passed to Whisper as an ISO-639-1 code, e.g. "zh" or
+    /// "en" (None = auto-detect)
+    #[serde(default)]
+    pub transcription_language: Option<String>,
+
+    /// Text passed as Whisper's `prompt` to steer spelling and style
+    #[serde(default)]
+    pub transcription_prompt: Option<String>,
+
+    /// Whisper sampling temperature, 0.0 to 1.0 (None = server default)
+    #[serde(default)]
+    pub transcription_temperature: Option<f32>,
+
+    /// Named sets of Whisper parameters that override the three settings
+    /// above while active
+    #[serde(default)]
+    pub transcription_profiles: Option<BTreeMap<String, TranscriptionProfile>>,
+
+    /// Name of the active entry in `transcription_profiles` (None = no overrides)
+    #[serde(default)]
+    pub active_transcription_profile: Option<String>,
+
+    /// What the main hotkey produces:
+    /// - "transcribe": text in the spoken language (default)
+    /// - "translate": English text via Whisper's translations endpoint
+    #[serde(default)]
+    pub transcription_mode: Option<String>,
+
+    /// Optional second global hotkey that always records in "translate" mode
+    #[serde(default)]
+    pub translation_hotkey: Option<String>,
+
+    /// Optional global hotkey for command mode: speak an instruction that is
+    /// applied to the selected text
+    #[serde(default)]
+    pub command_hotkey: Option<String>,
+
+    /// Custom vocabulary fed to the Whisper prompt and used to correct
+    /// misspelled terms in transcripts
+    #[serde(default)]
+    pub glossary: Option<Vec<GlossaryEntry>>,
+
+    /// Interpret spoken commands such as "comma", "new line" and "scratch
+    /// that" before typing (default: false, opt-in)
+    #[serde(default)]
+    pub spoken_commands_enabled: Option<bool>,
+
+    /// Replacement and snippet rules applied, in order, to the final text
+    /// before it is typed
+    #[serde(default)]
+    pub replacement_rules: Option<Vec<ReplacementRule>>,
+
+    /// Keep the raw transcript when the refined text no longer looks like
+    /// an edit of it, e.g. a dictated question got answered (default: true)
+    #[serde(default)]
+    pub refinement_drift_guard: Option<bool>,
+
+    /// Stream refinement output and type it as it arrives, when auto-type is
+    /// on (default: false)
+    #[serde(default)]
+    pub refinement_streaming: Option<bool>,
+
+    /// Named refinement setups (prompt, model, temperature, system message)
+    /// that override the global refinement settings while active
+    #[serde(default)]
+    pub refinement_presets: Option<BTreeMap<String, RefinementPreset>>,
+
+    /// Name of the active entry in `refinement_presets` (None = global settings)
+    #[serde(default)]
+    pub active_refinement_preset: Option<String>,
+
+    /// Overrides for dictating into particular apps, keyed by
+    /// [`app_profile_key`] of the app's bundle ID or process name
+    #[serde(default)]
+    pub app_profiles: Option<BTreeMap<String, AppProfile>>,
+
     // ---- Legacy fields kept for backwards compatibility (do not write new values) ----
     /// Legacy: Automatically insert the transcription.
     #[serde(default, skip_serializing)]
     pub auto_insert: Option<bool>,
 }
 
+/// Whisper parameters that override the global ones. Unset fields fall back
+/// to the global setting.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct TranscriptionProfile {
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+/// Refinement parameters that override the global ones. Unset fields fall
+/// back to the global setting (or the built-in default).
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct RefinementPreset {
+    /// Style/language preferences, like `refinement_prompt`
+    #[serde(default)]
+    pub prompt: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Chat sampling temperature, 0.0 to 2.0
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Replaces the built-in system message
+    #[serde(default)]
+    pub system_message: Option<String>,
+    /// Check the output against the transcript like `refinement_drift_guard`.
+    /// Unset follows the global setting, except that presets with their own
+    /// system message (notes, commit messages, ...) skip the check
+    #[serde(default)]
+    pub drift_guard: Option<bool>,
+}
+
+/// Settings applied when dictating into a particular app. Unset fields keep
+/// the global behavior.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct AppProfile {
+    /// Label shown in the UI, e.g. "Slack"
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Turn refinement on or off for this app
+    #[serde(default)]
+    pub refine: Option<bool>,
+    /// Refinement preset used instead of the active one
+    #[serde(default)]
+    pub refinement_preset: Option<String>,
+    /// Whisper language ("auto" to detect) used instead of the configured one
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Extra replacement rules, run after the global ones
+    #[serde(default)]
+    pub replacement_rules: Vec<ReplacementRule>,
+    /// How text is put into this app
+    #[serde(default)]
+    pub injection: Option<InjectionMethod>,
+}
+
+/// How dictated text gets into the focused app.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMethod {
+    /// Simulated key presses
+    #[default]
+    Type,
+    /// Put the text on the clipboard, press the paste shortcut, then restore
+    /// the previous clipboard contents
+    Paste,
+}
+
+/// Normalized app identifier used as the `app_profiles` key: lowercase,
+/// without a Windows `.exe` extension.
+pub fn app_profile_key(app_id: &str) -> String {
+    let key = app_id.trim().to_lowercase();
+    match key.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => key,
+    }
+}
+
 fn settings_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let dir = app
         .path()
@@ -152,6 +386,16 @@ pub fn get_type_speed_ms<R: Runtime>(app: &AppHandle<R>) -> Result<Option<u64>,
     Ok(load(app)?.type_speed_ms)
 }
 
+pub fn set_injection_method<R: Runtime>(app: &AppHandle<R>, method: InjectionMethod) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.injection_method = Some(method);
+    save(app, &s)
+}
+
+pub fn get_injection_method<R: Runtime>(app: &AppHandle<R>) -> Result<Option<InjectionMethod>, String> {
+    Ok(load(app)?.injection_method)
+}
+
 pub fn set_refine_output_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
     let mut s = load(app)?;
     s.refine_output_enabled = Some(enabled);
@@ -201,3 +445,672 @@ pub fn set_mic_gain<R: Runtime>(app: &AppHandle<R>, gain: f32) -> Result<(), Str
 pub fn get_mic_gain<R: Runtime>(app: &AppHandle<R>) -> Result<Option<f32>, String> {
     Ok(load(app)?.mic_gain)
 }
+
+pub fn set_input_device<R: Runtime>(app: &AppHandle<R>, name: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.input_device = non_empty(name);
+    save(app, &s)
+}
+
+pub fn get_input_device<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.input_device)
+}
+
+/// Trim `value`, mapping an empty string to `None`.
+pub(crate) fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+pub fn set_api_base_url<R: Runtime>(app: &AppHandle<R>, url: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let url = non_empty(url);
+    if let Some(u) = &url {
+        if !u.starts_with("http://") && !u.starts_with("https://") {
+            return Err("api_base_url must start with http:// or https://".into());
+        }
+    }
+    s.api_base_url = url;
+    save(app, &s)
+}
+
+pub fn get_api_base_url<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.api_base_url)
+}
+
+pub fn set_transcription_model<R: Runtime>(app: &AppHandle<R>, model: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.transcription_model = non_empty(model);
+    save(app, &s)
+}
+
+pub fn get_transcription_model<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.transcription_model)
+}
+
+pub fn set_extra_headers<R: Runtime>(
+    app: &AppHandle<R>,
+    headers: BTreeMap<String, String>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    let mut cleaned = BTreeMap::new();
+    for (name, value) in headers {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            continue;
+        }
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("Invalid header name: '{name}'"))?;
+        reqwest::header::HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("Invalid value for header '{name}'"))?;
+        cleaned.insert(name, value.trim().to_string());
+    }
+    s.extra_headers = if cleaned.is_empty() { None } else { Some(cleaned) };
+    save(app, &s)
+}
+
+pub fn get_extra_headers<R: Runtime>(app: &AppHandle<R>) -> Result<BTreeMap<String, String>, String> {
+    Ok(load(app)?.extra_headers.unwrap_or_default())
+}
+
+pub const TRANSCRIPTION_PROVIDERS: [&str; 3] = ["groq", "openai_compatible", "local_command"];
+
+pub fn set_transcription_provider<R: Runtime>(app: &AppHandle<R>, provider: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let p = provider.trim().to_lowercase();
+    if !TRANSCRIPTION_PROVIDERS.contains(&p.as_str()) {
+        return Err(format!(
+            "transcription_provider must be one of: {}",
+            TRANSCRIPTION_PROVIDERS.join(", ")
+        ));
+    }
+    s.transcription_provider = Some(p);
+    save(app, &s)
+}
+
+pub fn get_transcription_provider<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.transcription_provider)
+}
+
+pub fn set_openai_compatible_base_url<R: Runtime>(app: &AppHandle<R>, url: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.openai_compatible_base_url = non_empty(url);
+    save(app, &s)
+}
+
+pub fn get_openai_compatible_base_url<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.openai_compatible_base_url)
+}
+
+pub fn set_openai_compatible_model<R: Runtime>(app: &AppHandle<R>, model: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.openai_compatible_model = non_empty(model);
+    save(app, &s)
+}
+
+pub fn get_openai_compatible_model<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.openai_compatible_model)
+}
+
+pub fn set_openai_compatible_api_key<R: Runtime>(app: &AppHandle<R>, key: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.openai_compatible_api_key = non_empty(key);
+    save(app, &s)
+}
+
+pub fn get_openai_compatible_api_key<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.openai_compatible_api_key)
+}
+
+pub fn set_local_command<R: Runtime>(
+    app: &AppHandle<R>,
+    command: String,
+    args: Vec<String>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.local_command = non_empty(command);
+    s.local_command_args = Some(args);
+    save(app, &s)
+}
+
+pub fn get_local_command<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<(Option<String>, Vec<String>), String> {
+    let s = load(app)?;
+    Ok((s.local_command, s.local_command_args.unwrap_or_default()))
+}
+
+pub fn set_upload_encoding<R: Runtime>(app: &AppHandle<R>, encoding: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let available = crate::encode::UploadEncoding::available();
+    let parsed = crate::encode::UploadEncoding::parse(&encoding)
+        .filter(|e| e.is_available())
+        .ok_or_else(|| format!("upload_encoding must be one of: {}", available.join(", ")))?;
+    s.upload_encoding = Some(parsed.as_str().to_string());
+    save(app, &s)
+}
+
+pub fn get_upload_encoding<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.upload_encoding)
+}
+
+/// Allowed range for `max_segment_secs`.
+pub const MAX_SEGMENT_SECS_RANGE: std::ops::RangeInclusive<u32> = 10..=600;
+
+pub fn set_max_segment_secs<R: Runtime>(app: &AppHandle<R>, secs: u32) -> Result<(), String> {
+    if !MAX_SEGMENT_SECS_RANGE.contains(&secs) {
+        return Err(format!(
+            "max_segment_secs must be between {} and {}",
+            MAX_SEGMENT_SECS_RANGE.start(),
+            MAX_SEGMENT_SECS_RANGE.end()
+        ));
+    }
+    let mut s = load(app)?;
+    s.max_segment_secs = Some(secs);
+    save(app, &s)
+}
+
+pub fn get_max_segment_secs<R: Runtime>(app: &AppHandle<R>) -> Result<Option<u32>, String> {
+    Ok(load(app)?.max_segment_secs)
+}
+
+pub fn set_trim_silence_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.trim_silence_enabled = Some(enabled);
+    save(app, &s)
+}
+
+pub fn get_trim_silence_enabled<R: Runtime>(app: &AppHandle<R>) -> Result<Option<bool>, String> {
+    Ok(load(app)?.trim_silence_enabled)
+}
+
+pub fn set_hallucination_filter_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.hallucination_filter_enabled = Some(enabled);
+    save(app, &s)
+}
+
+pub fn get_hallucination_filter_enabled<R: Runtime>(app: &AppHandle<R>) -> Result<Option<bool>, String> {
+    Ok(load(app)?.hallucination_filter_enabled)
+}
+
+pub fn set_hallucination_blocklist<R: Runtime>(
+    app: &AppHandle<R>,
+    phrases: Option<Vec<String>>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.hallucination_blocklist = phrases.map(|list| list.into_iter().filter_map(non_empty).collect());
+    save(app, &s)
+}
+
+pub fn get_hallucination_blocklist<R: Runtime>(app: &AppHandle<R>) -> Result<Option<Vec<String>>, String> {
+    Ok(load(app)?.hallucination_blocklist)
+}
+
+/// Normalize a language code: empty or "auto" means auto-detect (`None`),
+/// anything else must be a two or three letter ISO-639 code.
+fn parse_language(language: String) -> Result<Option<String>, String> {
+    let code = language.trim().to_lowercase();
+    if code.is_empty() || code == "auto" {
+        return Ok(None);
+    }
+    if !(2..=3).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!(
+            "Invalid language '{}': use an ISO-639-1 code such as 'en' or 'zh', or 'auto'",
+            language.trim()
+        ));
+    }
+    Ok(Some(code))
+}
+
+fn check_temperature(temperature: Option<f32>) -> Result<Option<f32>, String> {
+    match temperature {
+        Some(t) if !(0.0..=1.0).contains(&t) => {
+            Err("transcription temperature must be between 0.0 and 1.0".into())
+        }
+        t => Ok(t),
+    }
+}
+
+pub fn set_transcription_language<R: Runtime>(app: &AppHandle<R>, language: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.transcription_language = parse_language(language)?;
+    save(app, &s)
+}
+
+pub fn get_transcription_language<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.transcription_language)
+}
+
+pub fn set_transcription_prompt<R: Runtime>(app: &AppHandle<R>, prompt: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.transcription_prompt = non_empty(prompt);
+    save(app, &s)
+}
+
+pub fn get_transcription_prompt<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.transcription_prompt)
+}
+
+pub fn set_transcription_temperature<R: Runtime>(
+    app: &AppHandle<R>,
+    temperature: Option<f32>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.transcription_temperature = check_temperature(temperature)?;
+    save(app, &s)
+}
+
+pub fn get_transcription_temperature<R: Runtime>(app: &AppHandle<R>) -> Result<Option<f32>, String> {
+    Ok(load(app)?.transcription_temperature)
+}
+
+pub fn get_transcription_profiles<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<BTreeMap<String, TranscriptionProfile>, String> {
+    Ok(load(app)?.transcription_profiles.unwrap_or_default())
+}
+
+/// Create or replace the profile called `name`.
+pub fn save_transcription_profile<R: Runtime>(
+    app: &AppHandle<R>,
+    name: String,
+    profile: TranscriptionProfile,
+) -> Result<(), String> {
+    let name = non_empty(name).ok_or("Profile name must not be empty")?;
+    let profile = TranscriptionProfile {
+        language: match profile.language {
+            Some(language) => parse_language(language)?,
+            None => None,
+        },
+        prompt: profile.prompt.and_then(non_empty),
+        temperature: check_temperature(profile.temperature)?,
+    };
+    let mut s = load(app)?;
+    s.transcription_profiles
+        .get_or_insert_with(BTreeMap::new)
+        .insert(name, profile);
+    save(app, &s)
+}
+
+/// Remove a profile, deactivating it if it was active.
+pub fn delete_transcription_profile<R: Runtime>(app: &AppHandle<R>, name: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let removed = s
+        .transcription_profiles
+        .as_mut()
+        .and_then(|profiles| profiles.remove(&name));
+    if removed.is_none() {
+        return Err(format!("No transcription profile named '{name}'"));
+    }
+    if s.active_transcription_profile.as_deref() == Some(name.as_str()) {
+        s.active_transcription_profile = None;
+    }
+    save(app, &s)
+}
+
+/// Select the active profile by name; `None` (or an empty name) clears it.
+pub fn set_active_transcription_profile<R: Runtime>(
+    app: &AppHandle<R>,
+    name: Option<String>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    let name = name.and_then(non_empty);
+    if let Some(n) = &name {
+        let exists = s
+            .transcription_profiles
+            .as_ref()
+            .is_some_and(|profiles| profiles.contains_key(n));
+        if !exists {
+            return Err(format!("No transcription profile named '{n}'"));
+        }
+    }
+    s.active_transcription_profile = name;
+    save(app, &s)
+}
+
+pub fn get_active_transcription_profile<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.active_transcription_profile)
+}
+
+pub fn set_transcription_mode<R: Runtime>(app: &AppHandle<R>, mode: String) -> Result<(), String> {
+    let task = crate::transcribe::TranscriptionTask::parse(&mode)
+        .ok_or("transcription_mode must be 'transcribe' or 'translate'")?;
+    let mut s = load(app)?;
+    s.transcription_mode = Some(task.as_str().to_string());
+    save(app, &s)
+}
+
+pub fn get_transcription_mode<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.transcription_mode)
+}
+
+pub fn set_translation_hotkey<R: Runtime>(app: &AppHandle<R>, hotkey: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.translation_hotkey = non_empty(hotkey);
+    save(app, &s)
+}
+
+pub fn get_translation_hotkey<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.translation_hotkey)
+}
+
+pub fn set_command_hotkey<R: Runtime>(app: &AppHandle<R>, hotkey: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.command_hotkey = non_empty(hotkey);
+    save(app, &s)
+}
+
+pub fn get_command_hotkey<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.command_hotkey)
+}
+
+pub fn get_glossary<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<GlossaryEntry>, String> {
+    Ok(load(app)?.glossary.unwrap_or_default())
+}
+
+/// Add or update a glossary entry. Terms are unique ignoring case; pass
+/// `previous_term` to rename an existing entry.
+pub fn save_glossary_entry<R: Runtime>(
+    app: &AppHandle<R>,
+    entry: GlossaryEntry,
+    previous_term: Option<String>,
+) -> Result<(), String> {
+    let entry = entry.cleaned().ok_or("Glossary term must not be empty")?;
+    let mut s = load(app)?;
+    let glossary = s.glossary.get_or_insert_with(Vec::new);
+
+    let previous = previous_term.and_then(non_empty);
+    let target = previous.as_deref().unwrap_or(&entry.term);
+    let existing = glossary.iter().position(|e| e.term.eq_ignore_ascii_case(target));
+    if previous.is_some() && existing.is_none() {
+        return Err(format!("No glossary entry for '{target}'"));
+    }
+    let clash = glossary
+        .iter()
+        .enumerate()
+        .any(|(i, e)| Some(i) != existing && e.term.eq_ignore_ascii_case(&entry.term));
+    if clash {
+        return Err(format!("A glossary entry for '{}' already exists", entry.term));
+    }
+
+    match existing {
+        Some(i) => glossary[i] = entry,
+        None => glossary.push(entry),
+    }
+    save(app, &s)
+}
+
+pub fn delete_glossary_entry<R: Runtime>(app: &AppHandle<R>, term: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let glossary = s.glossary.get_or_insert_with(Vec::new);
+    let before = glossary.len();
+    glossary.retain(|e| !e.term.eq_ignore_ascii_case(term.trim()));
+    if glossary.len() == before {
+        return Err(format!("No glossary entry for '{}'", term.trim()));
+    }
+    save(app, &s)
+}
+
+/// Merge `entries` into the glossary (entries with the same term are
+/// replaced), or replace the whole glossary if `replace_all` is set.
+/// Returns the number of entries imported.
+pub fn import_glossary<R: Runtime>(
+    app: &AppHandle<R>,
+    entries: Vec<GlossaryEntry>,
+    replace_all: bool,
+) -> Result<usize, String> {
+    let mut s = load(app)?;
+    let glossary = s.glossary.get_or_insert_with(Vec::new);
+    if replace_all {
+        glossary.clear();
+    }
+    let mut imported = 0;
+    for entry in entries.into_iter().filter_map(GlossaryEntry::cleaned) {
+        match glossary.iter_mut().find(|e| e.term.eq_ignore_ascii_case(&entry.term)) {
+            Some(existing) => *existing = entry,
+            None => glossary.push(entry),
+        }
+        imported += 1;
+    }
+    save(app, &s)?;
+    Ok(imported)
+}
+
+pub fn get_replacement_rules<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<ReplacementRule>, String> {
+    Ok(load(app)?.replacement_rules.unwrap_or_default())
+}
+
+/// Add a rule (when `rule.id` is 0) or update the rule with the same id.
+/// Rules whose pattern doesn't compile are rejected. Returns the saved rule.
+pub fn save_replacement_rule<R: Runtime>(
+    app: &AppHandle<R>,
+    mut rule: ReplacementRule,
+) -> Result<ReplacementRule, String> {
+    rule.name = rule.name.trim().to_string();
+    rule.compile().map_err(|e| e.to_string())?;
+
+    let mut s = load(app)?;
+    let rules = s.replacement_rules.get_or_insert_with(Vec::new);
+    if rule.id == 0 {
+        rule.id = rules.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        rules.push(rule.clone());
+    } else {
+        let existing = rules
+            .iter_mut()
+            .find(|r| r.id == rule.id)
+            .ok_or_else(|| format!("No replacement rule with id {}", rule.id))?;
+        *existing = rule.clone();
+    }
+    save(app, &s)?;
+    Ok(rule)
+}
+
+pub fn delete_replacement_rule<R: Runtime>(app: &AppHandle<R>, id: u64) -> Result<(), String> {
+    let mut s = load(app)?;
+    let rules = s.replacement_rules.get_or_insert_with(Vec::new);
+    let before = rules.len();
+    rules.retain(|r| r.id != id);
+    if rules.len() == before {
+        return Err(format!("No replacement rule with id {id}"));
+    }
+    save(app, &s)
+}
+
+pub fn set_replacement_rule_enabled<R: Runtime>(app: &AppHandle<R>, id: u64, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    let rule = s
+        .replacement_rules
+        .get_or_insert_with(Vec::new)
+        .iter_mut()
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("No replacement rule with id {id}"))?;
+    rule.enabled = enabled;
+    save(app, &s)
+}
+
+/// Reorder rules to match `ids`, which must list every rule exactly once.
+pub fn reorder_replacement_rules<R: Runtime>(app: &AppHandle<R>, ids: Vec<u64>) -> Result<(), String> {
+    let mut s = load(app)?;
+    let mut rules = s.replacement_rules.take().unwrap_or_default();
+    let mut ordered = Vec::with_capacity(rules.len());
+    for id in &ids {
+        let pos = rules
+            .iter()
+            .position(|r| r.id == *id)
+            .ok_or_else(|| format!("No replacement rule with id {id}"))?;
+        ordered.push(rules.remove(pos));
+    }
+    if !rules.is_empty() {
+        return Err("Rule order must include every rule".into());
+    }
+    s.replacement_rules = Some(ordered);
+    save(app, &s)
+}
+
+pub fn set_spoken_commands_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.spoken_commands_enabled = Some(enabled);
+    save(app, &s)
+}
+
+pub fn get_spoken_commands_enabled<R: Runtime>(app: &AppHandle<R>) -> Result<Option<bool>, String> {
+    Ok(load(app)?.spoken_commands_enabled)
+}
+
+pub fn set_refinement_drift_guard<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.refinement_drift_guard = Some(enabled);
+    save(app, &s)
+}
+
+pub fn get_refinement_drift_guard<R: Runtime>(app: &AppHandle<R>) -> Result<Option<bool>, String> {
+    Ok(load(app)?.refinement_drift_guard)
+}
+
+pub fn set_refinement_streaming<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let mut s = load(app)?;
+    s.refinement_streaming = Some(enabled);
+    save(app, &s)
+}
+
+pub fn get_refinement_streaming<R: Runtime>(app: &AppHandle<R>) -> Result<Option<bool>, String> {
+    Ok(load(app)?.refinement_streaming)
+}
+
+pub fn get_refinement_presets<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<BTreeMap<String, RefinementPreset>, String> {
+    Ok(load(app)?.refinement_presets.unwrap_or_default())
+}
+
+/// Create or replace the preset called `name`.
+pub fn save_refinement_preset<R: Runtime>(
+    app: &AppHandle<R>,
+    name: String,
+    preset: RefinementPreset,
+) -> Result<(), String> {
+    let name = non_empty(name).ok_or("Preset name must not be empty")?;
+    let preset = RefinementPreset {
+        prompt: preset.prompt.and_then(non_empty),
+        model: preset.model.and_then(non_empty),
+        temperature: match preset.temperature {
+            Some(t) if !(0.0..=2.0).contains(&t) => {
+                return Err("refinement temperature must be between 0.0 and 2.0".into())
+            }
+            t => t,
+        },
+        system_message: preset.system_message.and_then(non_empty),
+        drift_guard: preset.drift_guard,
+    };
+    let mut s = load(app)?;
+    s.refinement_presets
+        .get_or_insert_with(BTreeMap::new)
+        .insert(name, preset);
+    save(app, &s)
+}
+
+/// Remove a preset, deactivating it if it was active and clearing it from app
+/// profiles that use it.
+pub fn delete_refinement_preset<R: Runtime>(app: &AppHandle<R>, name: String) -> Result<(), String> {
+    let mut s = load(app)?;
+    let removed = s
+        .refinement_presets
+        .as_mut()
+        .and_then(|presets| presets.remove(&name));
+    if removed.is_none() {
+        return Err(format!("No refinement preset named '{name}'"));
+    }
+    if s.active_refinement_preset.as_deref() == Some(name.as_str()) {
+        s.active_refinement_preset = None;
+    }
+    for profile in s.app_profiles.iter_mut().flat_map(|profiles| profiles.values_mut()) {
+        if profile.refinement_preset.as_deref() == Some(name.as_str()) {
+            profile.refinement_preset = None;
+        }
+    }
+    save(app, &s)
+}
+
+/// Select the active preset by name; `None` (or an empty name) clears it.
+pub fn set_active_refinement_preset<R: Runtime>(
+    app: &AppHandle<R>,
+    name: Option<String>,
+) -> Result<(), String> {
+    let mut s = load(app)?;
+    let name = name.and_then(non_empty);
+    if let Some(n) = &name {
+        let exists = s
+            .refinement_presets
+            .as_ref()
+            .is_some_and(|presets| presets.contains_key(n));
+        if !exists {
+            return Err(format!("No refinement preset named '{n}'"));
+        }
+    }
+    s.active_refinement_preset = name;
+    save(app, &s)
+}
+
+pub fn get_active_refinement_preset<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    Ok(load(app)?.active_refinement_preset)
+}
+
+pub fn get_app_profiles<R: Runtime>(app: &AppHandle<R>) -> Result<BTreeMap<String, AppProfile>, String> {
+    Ok(load(app)?.app_profiles.unwrap_or_default())
+}
+
+/// Create or replace the profile for `app_id` (a bundle ID or process name).
+pub fn save_app_profile<R: Runtime>(app: &AppHandle<R>, app_id: String, profile: AppProfile) -> Result<(), String> {
+    let key = app_profile_key(&app_id);
+    if key.is_empty() {
+        return Err("App ID must not be empty".into());
+    }
+    let mut replacement_rules = Vec::new();
+    for (i, mut rule) in profile.replacement_rules.into_iter().enumerate() {
+        rule.compile().map_err(|e| format!("Replacement rule {}: {e}", i + 1))?;
+        // Ids only need to be unique within the profile.
+        rule.id = i as u64 + 1;
+        replacement_rules.push(rule);
+    }
+    let profile = AppProfile {
+        name: profile.name.and_then(non_empty),
+        refine: profile.refine,
+        refinement_preset: profile.refinement_preset.and_then(non_empty),
+        language: match profile.language {
+            // Unlike the global setting, "auto" is kept: it overrides a
+            // configured language with detection.
+            Some(language) if language.trim().eq_ignore_ascii_case("auto") => Some("auto".into()),
+            Some(language) => parse_language(language)?,
+            None => None,
+        },
+        replacement_rules,
+        injection: profile.injection,
+    };
+    let mut s = load(app)?;
+    if let Some(n) = &profile.refinement_preset {
+        let exists = s
+            .refinement_presets
+            .as_ref()
+            .is_some_and(|presets| presets.contains_key(n));
+        if !exists {
+            return Err(format!("No refinement preset named '{n}'"));
+        }
+    }
+    s.app_profiles.get_or_insert_with(BTreeMap::new).insert(key, profile);
+    save(app, &s)
+}
+
+pub fn delete_app_profile<R: Runtime>(app: &AppHandle<R>, app_id: String) -> Result<(), String> {
+    let key = app_profile_key(&app_id);
+    let mut s = load(app)?;
+    let removed = s.app_profiles.as_mut().and_then(|profiles| profiles.remove(&key));
+    if removed.is_none() {
+        return Err(format!("No app profile for '{app_id}'"));
+    }
+    save(app, &s)
+}
@@ -1,12 +1,181 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
 use reqwest::multipart;
 use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
 
+use crate::encode::{self, UploadEncoding};
+use crate::{chunking, http_client, refine};
+
+/// Groq's OpenAI-compatible API root.
+pub const GROQ_API_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Whisper model used by the Groq provider.
+pub const GROQ_TRANSCRIPTION_MODEL: &str = "whisper-large-v3";
+
+/// Where and how to reach an OpenAI-compatible API.
+///
+/// Shared by the Whisper and chat calls so a custom gateway (or a local mock
+/// server) only needs to be configured once.
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// API root without a trailing path, e.g. `https://api.groq.com/openai/v1`.
+    pub base_url: String,
+    /// Sent as a bearer token when present.
+    pub api_key: Option<String>,
+    /// Additional headers sent with every request (e.g. gateway auth/routing).
+    pub extra_headers: BTreeMap<String, String>,
+}
+
+impl ApiConfig {
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim().trim_end_matches('/'), path)
+    }
+
+    fn apply(&self, mut req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(key) = self.api_key.as_deref().filter(|k| !k.trim().is_empty()) {
+            req = req.bearer_auth(key);
+        }
+        for (name, value) in &self.extra_headers {
+            req = req.header(name, value);
+        }
+        req
+    }
+}
+
+/// Largest file Groq and OpenAI accept on `/audio/transcriptions`.
+pub const MAX_UPLOAD_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Default longest segment sent in one request; longer recordings are split.
+pub const DEFAULT_MAX_SEGMENT: Duration = Duration::from_secs(120);
+
+/// Audio repeated at the start of a segment when it couldn't be cut at silence.
+const SEGMENT_OVERLAP: Duration = Duration::from_secs(1);
+
+/// Segments transcribed at the same time.
+const MAX_CONCURRENT_SEGMENTS: usize = 3;
+
+/// How recordings are prepared for the API providers.
+#[derive(Debug, Clone, Copy)]
+pub struct UploadOptions {
+    pub encoding: UploadEncoding,
+    /// Recordings longer than this are split at quiet points.
+    pub max_segment: Duration,
+}
+
+/// What Whisper should produce from the audio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranscriptionTask {
+    /// Text in the spoken language (`/audio/transcriptions`).
+    #[default]
+    Transcribe,
+    /// English text, whatever the spoken language (`/audio/translations`).
+    Translate,
+}
+
+impl TranscriptionTask {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "transcribe" => Some(Self::Transcribe),
+            "translate" => Some(Self::Translate),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Transcribe => "transcribe",
+            Self::Translate => "translate",
+        }
+    }
+
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Transcribe => "audio/transcriptions",
+            Self::Translate => "audio/translations",
+        }
+    }
+}
+
+/// Optional Whisper request parameters.
+#[derive(Debug, Clone, Default)]
+pub struct WhisperOptions {
+    pub task: TranscriptionTask,
+    /// ISO-639-1 code of the spoken language; `None` lets Whisper detect it.
+    /// Not sent for translations, which only take English output.
+    pub language: Option<String>,
+    /// Text that steers spelling and style (e.g. product vocabulary).
+    pub prompt: Option<String>,
+    pub temperature: Option<f32>,
+}
+
+/// Chat completion parameters for refinement.
+#[derive(Debug, Clone)]
+pub struct RefinementOptions {
+    pub model: String,
+    /// Style/language preferences sent along with the transcript.
+    pub prompt: String,
+    pub temperature: f32,
+    /// Replaces [`refine::SYSTEM_PROMPT`].
+    pub system_message: Option<String>,
+    /// Whether the result is checked with [`refine::check_drift`]. Not sent.
+    pub drift_guard: bool,
+}
+
+/// Placeholder in local command arguments that is replaced with the WAV path.
+pub const LOCAL_COMMAND_WAV_PLACEHOLDER: &str = "{wav}";
+
+/// Transcription response. With `response_format=verbose_json` it also
+/// carries per-segment details; servers that ignore the format return only
+/// `text`.
 #[derive(Debug, Deserialize)]
 struct GroqTranscriptionResponse {
     text: String,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    segments: Vec<TranscriptSegment>,
+}
+
+/// Text returned by a transcription provider.
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    /// Language reported by a `verbose_json` response (a name such as
+    /// "english" or a code, depending on the server). For translations this
+    /// is the spoken language, not the output language.
+    pub language: Option<String>,
+    /// Whisper's per-segment statistics. Empty for providers (or servers)
+    /// that only return plain text.
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl Transcript {
+    pub fn from_text(text: String) -> Self {
+        Self {
+            text,
+            language: None,
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// One segment of a `verbose_json` transcription response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranscriptSegment {
+    /// Whisper's estimate that the segment contains no speech.
+    #[serde(default)]
+    pub no_speech_prob: Option<f32>,
+    /// Average token log-probability; very low values mean Whisper was guessing.
+    #[serde(default)]
+    pub avg_logprob: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,104 +193,518 @@ struct GroqMessage {
     content: String,
 }
 
-pub async fn transcribe_groq(wav_path: PathBuf, api_key: String) -> Result<String> {
-    if api_key.trim().is_empty() {
-        return Err(anyhow!("Missing Groq API key"));
+/// One `data:` payload of a streamed chat completion.
+#[derive(Debug, Deserialize)]
+struct GroqChatChunk {
+    #[serde(default)]
+    choices: Vec<GroqChunkChoice>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqChunkChoice {
+    #[serde(default)]
+    delta: GroqDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GroqDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Transcription providers
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A speech-to-text backend that turns a recorded WAV file into text.
+///
+/// `do_transcription_pipeline` picks the implementation from settings
+/// (`transcription_provider`), so new backends only need to implement this.
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Human-readable name used in log events (e.g. "Groq").
+    fn name(&self) -> &'static str;
+
+    /// Model (or executable) recorded alongside results in history.
+    fn model(&self) -> &str;
+
+    /// Transcribe the WAV file at `wav_path`.
+    async fn transcribe(&self, wav_path: PathBuf) -> Result<Transcript>;
+}
+
+/// Groq's hosted Whisper (`whisper-large-v3` by default).
+pub struct GroqProvider {
+    pub api: ApiConfig,
+    pub model: String,
+    pub upload: UploadOptions,
+    pub whisper: WhisperOptions,
+}
+
+#[async_trait]
+impl TranscriptionProvider for GroqProvider {
+    fn name(&self) -> &'static str {
+        "Groq"
     }
 
-    let bytes = tokio::fs::read(&wav_path)
-        .await
-        .with_context(|| format!("Failed to read audio file: {}", wav_path.display()))?;
-
-    let file_part = multipart::Part::bytes(bytes)
-        .file_name(
-            wav_path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("audio.wav")
-                .to_string(),
-        )
-        .mime_str("audio/wav")?;
-
-    // Groq OpenAI-compatible endpoint
-    let form = multipart::Form::new()
-        .text("model", "whisper-large-v3")
-        .part("file", file_part);
-
-    let client = reqwest::Client::new();
-    let res = client
-        .post("https://api.groq.com/openai/v1/audio/transcriptions")
-        .bearer_auth(api_key)
-        .multipart(form)
-        .send()
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn transcribe(&self, wav_path: PathBuf) -> Result<Transcript> {
+        transcribe_groq(wav_path, &self.api, &self.model, self.upload, &self.whisper).await
+    }
+}
+
+/// Any server exposing the OpenAI `/audio/transcriptions` endpoint
+/// (OpenAI itself, a self-hosted faster-whisper server, LocalAI, ...).
+pub struct OpenAiCompatibleProvider {
+    /// API root, e.g. `http://localhost:8000/v1`. The API key is optional:
+    /// many self-hosted servers don't require authentication.
+    pub api: ApiConfig,
+    pub model: String,
+    pub upload: UploadOptions,
+    pub whisper: WhisperOptions,
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &'static str {
+        "OpenAI-compatible server"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn transcribe(&self, wav_path: PathBuf) -> Result<Transcript> {
+        if self.api.base_url.trim().is_empty() {
+            return Err(anyhow!("Missing base URL for the OpenAI-compatible provider"));
+        }
+        transcribe_openai_compatible(&self.api, &self.model, &wav_path, self.upload, &self.whisper).await
+    }
+}
+
+/// Runs a user-configured executable (e.g. whisper.cpp's `whisper-cli`) and
+/// reads the transcript from its stdout.
+///
+/// If any argument contains `{wav}`, it is replaced with the WAV path.
+/// Otherwise the WAV bytes are piped into the process's stdin.
+///
+/// Example for whisper.cpp:
+/// `whisper-cli -m ggml-base.en.bin -f {wav} -nt`
+pub struct LocalCommandProvider {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+#[async_trait]
+impl TranscriptionProvider for LocalCommandProvider {
+    fn name(&self) -> &'static str {
+        "local command"
+    }
+
+    fn model(&self) -> &str {
+        &self.command
+    }
+
+    async fn transcribe(&self, wav_path: PathBuf) -> Result<Transcript> {
+        if self.command.trim().is_empty() {
+            return Err(anyhow!("Missing executable for the local command provider"));
+        }
+
+        let wav_str = wav_path.to_string_lossy();
+        let pass_path = self
+            .args
+            .iter()
+            .any(|a| a.contains(LOCAL_COMMAND_WAV_PLACEHOLDER));
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| a.replace(LOCAL_COMMAND_WAV_PLACEHOLDER, &wav_str))
+            .collect();
+
+        let mut child = tokio::process::Command::new(&self.command)
+            .args(&args)
+            .stdin(if pass_path { Stdio::null() } else { Stdio::piped() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start local command: {}", self.command))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let bytes = tokio::fs::read(&wav_path)
+                .await
+                .with_context(|| format!("Failed to read audio file: {}", wav_path.display()))?;
+            // Write from a separate task so a chatty child can't deadlock on a full stdout pipe.
+            tokio::spawn(async move {
+                let _ = stdin.write_all(&bytes).await;
+            });
+        }
+
+        let output = child
+            .wait_with_output()
+            .await
+            .context("Local command failed")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!(
+                "Local command exited with {}: {}",
+                output.status,
+                stderr.trim()
+            ));
+        }
+
+        Ok(Transcript::from_text(
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ))
+    }
+}
+
+/// Upload the recording and return its transcript.
+///
+/// Long recordings are split at quiet points into segments of at most
+/// `upload.max_segment`, each re-encoded (and if needed further split) to fit
+/// [`MAX_UPLOAD_BYTES`]. Segments are transcribed concurrently, at most
+/// [`MAX_CONCURRENT_SEGMENTS`] at a time, and stitched back together in order.
+/// Every segment is sent with the same `whisper` parameters.
+async fn transcribe_openai_compatible(
+    api: &ApiConfig,
+    model: &str,
+    wav_path: &Path,
+    upload: UploadOptions,
+    whisper: &WhisperOptions,
+) -> Result<Transcript> {
+    let path = wav_path.to_path_buf();
+    let parts = tokio::task::spawn_blocking(move || prepare_segments(&path, upload))
         .await
-        .context("Groq request failed")?;
+        .context("Audio encoding task failed")??;
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_SEGMENTS));
+    let mut tasks: Vec<_> = parts
+        .into_iter()
+        .map(|(audio, overlaps_previous)| {
+            let api = api.clone();
+            let model = model.to_string();
+            let whisper = whisper.clone();
+            let semaphore = semaphore.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                let transcript = upload_for_transcription(&api, &model, &whisper, &audio).await?;
+                Ok::<_, anyhow::Error>((transcript, overlaps_previous))
+            })
+        })
+        .collect();
+
+    let total = tasks.len();
+    let mut texts = Vec::with_capacity(total);
+    let mut segments = Vec::new();
+    let mut language = None;
+    for i in 0..total {
+        let result = match (&mut tasks[i]).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow!("Transcription task failed: {e}")),
+        };
+        match result {
+            Ok((transcript, overlaps_previous)) => {
+                texts.push((transcript.text, overlaps_previous));
+                segments.extend(transcript.segments);
+                language = language.or(transcript.language);
+            }
+            Err(e) => {
+                // Don't keep uploading the rest once one segment has failed.
+                tasks[i + 1..].iter().for_each(|t| t.abort());
+                return Err(if total > 1 {
+                    e.context(format!("Segment {} of {total}", i + 1))
+                } else {
+                    e
+                });
+            }
+        }
+    }
+
+    Ok(Transcript {
+        text: chunking::stitch(&texts),
+        language,
+        segments,
+    })
+}
+
+/// Read, segment and encode a recording. Returns each payload with whether
+/// it overlaps the previous one.
+fn prepare_segments(wav_path: &Path, upload: UploadOptions) -> Result<Vec<(encode::EncodedAudio, bool)>> {
+    let (samples, sample_rate) = encode::read_wav_mono(wav_path)?;
+    let stem = wav_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("audio")
+        .to_string();
+
+    let max_len = (upload.max_segment.as_secs_f64() * sample_rate as f64) as usize;
+    let overlap = (SEGMENT_OVERLAP.as_secs_f64() * sample_rate as f64) as usize;
+    let segments = chunking::split_at_silence(&samples, sample_rate, max_len, overlap);
+
+    let mut parts = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let segment_stem = if segments.len() > 1 {
+            format!("{stem}-{}", i + 1)
+        } else {
+            stem.clone()
+        };
+        let encoded = encode::prepare_upload(
+            &samples[segment.range.clone()],
+            sample_rate,
+            &segment_stem,
+            upload.encoding,
+            MAX_UPLOAD_BYTES,
+        )?;
+        for (j, audio) in encoded.into_iter().enumerate() {
+            parts.push((audio, j == 0 && segment.overlaps_previous));
+        }
+    }
+    Ok(parts)
+}
+
+async fn upload_for_transcription(
+    api: &ApiConfig,
+    model: &str,
+    whisper: &WhisperOptions,
+    audio: &encode::EncodedAudio,
+) -> Result<Transcript> {
+    let url = api.endpoint(whisper.task.endpoint());
+
+    // The multipart form is rebuilt per attempt since it can't be cloned.
+    let res = http_client::send_with_retry("Transcription", || {
+        let file_part = multipart::Part::bytes(audio.bytes.clone())
+            .file_name(audio.file_name.clone())
+            .mime_str(audio.mime)?;
+
+        let mut form = multipart::Form::new()
+            .text("model", model.to_string())
+            .text("response_format", "verbose_json")
+            .part("file", file_part);
+        if let (Some(language), TranscriptionTask::Transcribe) = (&whisper.language, whisper.task) {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = &whisper.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
+        if let Some(temperature) = whisper.temperature {
+            form = form.text("temperature", temperature.to_string());
+        }
+
+        Ok(api.apply(http_client::client().post(&url).multipart(form)))
+    })
+    .await
+    .with_context(|| format!("Transcription request to {url} failed"))?;
 
     if !res.status().is_success() {
         let status = res.status();
         let body = res.text().await.unwrap_or_default();
-        return Err(anyhow!("Groq transcription failed: {status} {body}"));
+        return Err(anyhow!("Transcription failed: {status} {body}"));
     }
 
     let parsed: GroqTranscriptionResponse = res
         .json()
         .await
-        .context("Failed to parse Groq response JSON")?;
-    Ok(parsed.text)
+        .context("Failed to parse transcription response JSON")?;
+    Ok(Transcript {
+        text: parsed.text,
+        language: parsed.language,
+        segments: parsed.segments,
+    })
+}
+
+pub async fn transcribe_groq(
+    wav_path: PathBuf,
+    api: &ApiConfig,
+    model: &str,
+    upload: UploadOptions,
+    whisper: &WhisperOptions,
+) -> Result<Transcript> {
+    if api.api_key.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(anyhow!("Missing Groq API key"));
+    }
+
+    // Groq OpenAI-compatible endpoint (or a gateway in front of it)
+    transcribe_openai_compatible(api, model, &wav_path, upload, whisper)
+        .await
+        .map_err(|e| anyhow!("Groq: {e:#}"))
 }
 
-pub async fn refine_transcript(api_key: String, transcript: String, custom_prompt: String, model: String) -> Result<String> {
-    if api_key.trim().is_empty() {
+pub async fn refine_transcript(api: &ApiConfig, transcript: String, options: &RefinementOptions) -> Result<String> {
+    if api.api_key.as_deref().unwrap_or("").trim().is_empty() {
         return Err(anyhow!("Missing Groq API key"));
     }
 
-    let base_instruction = "Please improve the following transcript by fixing grammar, punctuation, and making it more readable while preserving the original meaning. Apply any style or language preferences specified in the next section.";
-    
-    let full_prompt = format!(
-        "{}\n\nStyle/Language Preferences:\n{}\n\nOriginal Transcript:\n<transcript>\n{}\n</transcript>",
-        base_instruction,
-        if custom_prompt.trim().is_empty() { "No specific style preferences." } else { &custom_prompt },
-        transcript
-    );
+    let request_body = refinement_request(&transcript, options, false);
+    let content = chat_completion(api, "Refinement", &request_body).await?;
+
+    // Reasoning, echoed tags and preamble must never be typed.
+    let refined = refine::sanitize(&content);
+    if refined.is_empty() {
+        return Err(anyhow!("Refinement returned no text after removing reasoning"));
+    }
+    Ok(refined)
+}
 
-    let client = reqwest::Client::new();
-    let request_body = serde_json::json!({
-        "model": model,
+/// Command mode: apply a spoken `instruction` to the selected text with the
+/// chat model. Uses the model and temperature from `options`; the style
+/// prompt and system message are refinement-only.
+pub async fn edit_selection(
+    api: &ApiConfig,
+    selection: &str,
+    instruction: &str,
+    options: &RefinementOptions,
+) -> Result<String> {
+    if api.api_key.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(anyhow!("Missing Groq API key"));
+    }
+
+    let mut request_body = serde_json::json!({
+        "model": options.model,
         "messages": [
-            {
-                "role": "system",
-                "content": "You are a helpful assistant that refines and improves text transcripts."
-            },
-            {
-                "role": "user",
-                "content": full_prompt
-            }
+            { "role": "system", "content": refine::COMMAND_SYSTEM_PROMPT },
+            { "role": "user", "content": refine::command_message(selection, instruction) }
         ],
-        "temperature": 0.1
+        "temperature": options.temperature
     });
+    if let Some(body) = request_body.as_object_mut() {
+        body.extend(refine::reasoning_options(&options.model));
+    }
+    let content = chat_completion(api, "Command", &request_body).await?;
 
-    let res = client
-        .post("https://api.groq.com/openai/v1/chat/completions")
-        .bearer_auth(api_key)
-        .json(&request_body)
-        .send()
-        .await
-        .context("Groq chat request failed")?;
+    let edited = refine::sanitize(&content);
+    if edited.is_empty() {
+        return Err(anyhow!("Command returned no text after removing reasoning"));
+    }
+    Ok(edited)
+}
+
+/// Send a (non-streaming) chat completion request and return the first
+/// choice's message content. `label` names the request in logs and errors.
+async fn chat_completion(api: &ApiConfig, label: &str, request_body: &serde_json::Value) -> Result<String> {
+    let url = api.endpoint("chat/completions");
+    let res = http_client::send_with_retry(label, || {
+        Ok(api.apply(http_client::client().post(&url).json(request_body)))
+    })
+    .await
+    .context("Groq chat request failed")?;
 
     if !res.status().is_success() {
         let status = res.status();
         let body = res.text().await.unwrap_or_default();
-        return Err(anyhow!("Groq refinement failed: {status} {body}"));
+        return Err(anyhow!("Groq {} failed: {status} {body}", label.to_lowercase()));
     }
 
     let parsed: GroqChatResponse = res
         .json()
         .await
         .context("Failed to parse Groq chat response JSON")?;
-    
-    if parsed.choices.is_empty() {
-        return Err(anyhow!("No choices returned from Groq API"));
+
+    match parsed.choices.into_iter().next() {
+        Some(choice) => Ok(choice.message.content),
+        None => Err(anyhow!("No choices returned from Groq API")),
+    }
+}
+
+/// Like [`refine_transcript`], but with `stream: true`: sanitized text is
+/// passed to `on_text` as it arrives. Returns the whole refined text.
+///
+/// `on_text` returns `false` to stop reading the stream, which makes this
+/// return an error. A stream that breaks off before `[DONE]` is an error too,
+/// so callers can tell a complete refinement from a truncated one.
+pub async fn refine_transcript_stream<F>(
+    api: &ApiConfig,
+    transcript: String,
+    options: &RefinementOptions,
+    mut on_text: F,
+) -> Result<String>
+where
+    F: FnMut(&str) -> bool,
+{
+    if api.api_key.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(anyhow!("Missing Groq API key"));
+    }
+
+    let request_body = refinement_request(&transcript, options, true);
+    let url = api.endpoint("chat/completions");
+    let mut res = http_client::send_with_retry("Refinement", || {
+        Ok(api.apply(http_client::client().post(&url).json(&request_body)))
+    })
+    .await
+    .context("Groq chat request failed")?;
+
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(anyhow!("Groq refinement failed: {status} {body}"));
+    }
+
+    let mut decoder = refine::SseDecoder::default();
+    let mut sanitizer = refine::StreamSanitizer::default();
+    let mut done = false;
+    while !done {
+        let Some(chunk) = res.chunk().await.context("Refinement stream interrupted")? else {
+            break;
+        };
+        for payload in decoder.push(&chunk) {
+            if payload == "[DONE]" {
+                done = true;
+                break;
+            }
+            let parsed: GroqChatChunk = serde_json::from_str(&payload)
+                .context("Failed to parse Groq chat stream chunk")?;
+            if let Some(error) = parsed.error {
+                return Err(anyhow!("Groq refinement failed mid-stream: {error}"));
+            }
+            let Some(delta) = parsed.choices.into_iter().next().and_then(|c| c.delta.content) else {
+                continue;
+            };
+            let text = sanitizer.push(&delta)?;
+            if !text.is_empty() && !on_text(&text) {
+                return Err(anyhow!("Refinement stream stopped"));
+            }
+        }
+    }
+    if !done {
+        return Err(anyhow!("Refinement stream ended early"));
+    }
+
+    let text = sanitizer.finish()?;
+    if !text.is_empty() && !on_text(&text) {
+        return Err(anyhow!("Refinement stream stopped"));
+    }
+    if sanitizer.text().is_empty() {
+        return Err(anyhow!("Refinement returned no text after removing reasoning"));
+    }
+    Ok(sanitizer.text().to_string())
+}
+
+/// Chat completion request for refining `transcript`.
+fn refinement_request(transcript: &str, options: &RefinementOptions, stream: bool) -> serde_json::Value {
+    let mut request_body = serde_json::json!({
+        "model": options.model,
+        "messages": [
+            {
+                "role": "system",
+                "content": options.system_message.as_deref().unwrap_or(refine::SYSTEM_PROMPT)
+            },
+            {
+                "role": "user",
+                "content": refine::user_message(transcript, &options.prompt)
+            }
+        ],
+        "temperature": options.temperature
+    });
+    if let Some(body) = request_body.as_object_mut() {
+        if stream {
+            body.insert("stream".into(), serde_json::Value::Bool(true));
+        }
+        body.extend(refine::reasoning_options(&options.model));
     }
-    
-    Ok(parsed.choices[0].message.content.clone())
+    request_body
 }
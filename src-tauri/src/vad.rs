@@ -0,0 +1,220 @@
+//! Voice activity detection for trimming silence before upload.
+//!
+//! Hold-to-talk recordings include the silence between the key press and the
+//! first word (and after the last one). Besides wasting upload time, long
+//! silence makes Whisper hallucinate phrases like "Thank you.".
+//!
+//! Frames are classified by energy against an adaptive noise floor, with a
+//! zero-crossing-rate check so quiet fricatives ("s", "f") still count as
+//! speech. Short bursts such as key clicks are ignored.
+
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::{audio, encode};
+
+/// Analysis frame length.
+const FRAME_MS: usize = 20;
+
+/// The quietest 10% of frames estimate the noise floor.
+const NOISE_PERCENTILE: f64 = 0.1;
+
+/// Noise floor bounds (RMS, 16-bit scale). The upper bound keeps a recording
+/// that is speech from start to end from raising the threshold above speech.
+const NOISE_FLOOR_MIN: f64 = 20.0;
+const NOISE_FLOOR_MAX: f64 = 600.0;
+
+/// Speech must be this many times louder than the noise floor...
+const SPEECH_TO_NOISE: f64 = 4.0;
+/// ...and at least this loud (about -44 dBFS).
+const MIN_SPEECH_RMS: f64 = 200.0;
+
+/// Unvoiced consonants are quiet but cross zero often; frames within this
+/// zero-crossing rate need only half the energy threshold.
+const FRICATIVE_ZCR: Range<f64> = 0.25..0.75;
+
+/// Consecutive speech frames needed to count as speech (rejects clicks).
+const MIN_SPEECH_FRAMES: usize = 4;
+
+/// Audio kept around the detected speech so word edges aren't clipped.
+const PAD_BEFORE_MS: usize = 200;
+const PAD_AFTER_MS: usize = 300;
+
+/// Outcome of [`trim_silence`].
+#[derive(Debug, Clone, Copy)]
+pub struct TrimReport {
+    /// Whether any speech was found. If not, the file is left unchanged.
+    pub has_speech: bool,
+    pub original_ms: u64,
+    pub removed_ms: u64,
+}
+
+/// Trim leading and trailing silence from the WAV file at `path`, rewriting
+/// it in place as 16 kHz mono.
+pub fn trim_silence(path: &Path) -> Result<TrimReport> {
+    let (samples, sample_rate) = encode::read_wav_mono(path)?;
+    let to_ms = |n: usize| n as u64 * 1000 / sample_rate.max(1) as u64;
+    let original_ms = to_ms(samples.len());
+
+    let Some(speech) = detect_speech(&samples, sample_rate) else {
+        return Ok(TrimReport {
+            has_speech: false,
+            original_ms,
+            removed_ms: 0,
+        });
+    };
+
+    let removed = samples.len() - speech.len();
+    if removed > 0 {
+        audio::write_wav(path, &samples[speech], sample_rate, 1)?;
+    }
+    Ok(TrimReport {
+        has_speech: true,
+        original_ms,
+        removed_ms: to_ms(removed),
+    })
+}
+
+/// The sample range from the first to the last speech (plus padding), or
+/// `None` if the recording contains no speech.
+pub fn detect_speech(samples: &[i16], sample_rate: u32) -> Option<Range<usize>> {
+    let frame = (sample_rate as usize * FRAME_MS / 1000).max(1);
+    let frames: Vec<(f64, f64)> = samples
+        .chunks_exact(frame)
+        .map(|f| (rms(f), zero_crossing_rate(f)))
+        .collect();
+    if frames.is_empty() {
+        return None;
+    }
+
+    let threshold = (noise_floor(&frames) * SPEECH_TO_NOISE).max(MIN_SPEECH_RMS);
+    let is_speech: Vec<bool> = frames
+        .iter()
+        .map(|&(rms, zcr)| {
+            rms >= threshold || (rms >= threshold / 2.0 && FRICATIVE_ZCR.contains(&zcr))
+        })
+        .collect();
+
+    let first = first_run_start(is_speech.iter().copied())?;
+    let last = is_speech.len() - first_run_start(is_speech.iter().rev().copied())?;
+
+    let pad_before = sample_rate as usize * PAD_BEFORE_MS / 1000;
+    let pad_after = sample_rate as usize * PAD_AFTER_MS / 1000;
+    let start = (first * frame).saturating_sub(pad_before);
+    let end = (last * frame + pad_after).min(samples.len());
+    Some(start..end)
+}
+
+/// Index of the first frame of the first run of `MIN_SPEECH_FRAMES` speech frames.
+fn first_run_start(frames: impl Iterator<Item = bool>) -> Option<usize> {
+    let mut run = 0;
+    for (i, speech) in frames.enumerate() {
+        run = if speech { run + 1 } else { 0 };
+        if run == MIN_SPEECH_FRAMES {
+            return Some(i + 1 - MIN_SPEECH_FRAMES);
+        }
+    }
+    None
+}
+
+fn noise_floor(frames: &[(f64, f64)]) -> f64 {
+    let mut levels: Vec<f64> = frames.iter().map(|&(rms, _)| rms).collect();
+    levels.sort_by(f64::total_cmp);
+    let idx = ((levels.len() - 1) as f64 * NOISE_PERCENTILE) as usize;
+    levels[idx].clamp(NOISE_FLOOR_MIN, NOISE_FLOOR_MAX)
+}
+
+fn rms(samples: &[i16]) -> f64 {
+    let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / samples.len() as f64).sqrt()
+}
+
+fn zero_crossing_rate(samples: &[i16]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|w| (w[0] >= 0) != (w[1] >= 0))
+        .count();
+    crossings as f64 / (samples.len() - 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+    const MS: usize = RATE as usize / 1000;
+
+    /// Deterministic low-level noise in [-amplitude, amplitude].
+    fn noise(len: usize, amplitude: i32) -> Vec<i16> {
+        let mut x: u32 = 0x1234_5678;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                ((x % (2 * amplitude as u32 + 1)) as i32 - amplitude) as i16
+            })
+            .collect()
+    }
+
+    fn add_tone(samples: &mut [i16], range: Range<usize>, amplitude: f32) {
+        for i in range {
+            let tone = (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / RATE as f32).sin() * amplitude;
+            samples[i] = samples[i].saturating_add(tone as i16);
+        }
+    }
+
+    #[test]
+    fn silence_has_no_speech() {
+        assert_eq!(detect_speech(&vec![0; 3000 * MS], RATE), None);
+        assert_eq!(detect_speech(&noise(3000 * MS, 80), RATE), None);
+    }
+
+    #[test]
+    fn empty_or_tiny_input_has_no_speech() {
+        assert_eq!(detect_speech(&[], RATE), None);
+        assert_eq!(detect_speech(&[1000; 10], RATE), None);
+    }
+
+    #[test]
+    fn key_click_is_not_speech() {
+        let mut samples = noise(2000 * MS, 50);
+        add_tone(&mut samples, 500 * MS..515 * MS, 20_000.0);
+        assert_eq!(detect_speech(&samples, RATE), None);
+    }
+
+    #[test]
+    fn trims_to_speech_with_padding() {
+        let mut samples = noise(5000 * MS, 60);
+        add_tone(&mut samples, 1500 * MS..3000 * MS, 6000.0);
+
+        let range = detect_speech(&samples, RATE).unwrap();
+        let expected_start = 1500 * MS - PAD_BEFORE_MS * MS;
+        let expected_end = 3000 * MS + PAD_AFTER_MS * MS;
+        let tolerance = 2 * FRAME_MS * MS;
+        assert!(range.start.abs_diff(expected_start) <= tolerance, "start {}", range.start);
+        assert!(range.end.abs_diff(expected_end) <= tolerance, "end {}", range.end);
+    }
+
+    #[test]
+    fn speech_throughout_is_kept() {
+        let mut samples = vec![0; 2000 * MS];
+        add_tone(&mut samples, 0..2000 * MS, 5000.0);
+        assert_eq!(detect_speech(&samples, RATE), Some(0..samples.len()));
+    }
+
+    #[test]
+    fn quiet_fricatives_count_as_speech() {
+        let mut samples = noise(3000 * MS, 40);
+        // High-ZCR noise below the energy threshold but above half of it.
+        let hiss = noise(300 * MS, 280);
+        samples[1000 * MS..1300 * MS].copy_from_slice(&hiss);
+        let range = detect_speech(&samples, RATE).unwrap();
+        assert!(range.start <= 1000 * MS && range.end >= 1300 * MS);
+    }
+}
@@ -115,7 +115,8 @@ function App() {
       setRecordingState("processing");
       try {
         const text = await invoke<string>("stop_and_transcribe");
-        setTranscription(text);
+        // Empty when no speech was detected.
+        if (text) setTranscription(text);
       } catch (err) {
         addLog("error", `Transcription failed: ${err}`);
       }